@@ -0,0 +1,206 @@
+use super::{parse_clock_duration, to_utc, ImportBatch, ImportedSession, Position, RowError};
+use crate::models::{names_match, MetronData, Session};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use chrono_tz::Tz;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+
+/// Fields that can be mapped to columns. `date` and `time` are an alternative
+/// to `start` for sheets that keep them in separate columns.
+pub const FIELDS: [&str; 9] = ["id", "title", "category", "tags", "start", "end", "duration", "date", "time"];
+
+/// Fields written on export, in this order
+const EXPORT_FIELDS: [&str; 7] = ["id", "title", "category", "tags", "start", "end", "duration"];
+
+/// Which column holds which field. Columns are found by header, ignoring case;
+/// by default each field is expected in a column of the same name.
+#[derive(Debug, Clone)]
+pub struct ColumnMap {
+    columns: HashMap<&'static str, String>,
+}
+
+impl Default for ColumnMap {
+    fn default() -> Self {
+        Self { columns: FIELDS.iter().map(|f| (*f, f.to_string())).collect() }
+    }
+}
+
+impl ColumnMap {
+    pub fn set(&mut self, field: &str, column: &str) -> Result<(), String> {
+        let field = FIELDS.iter()
+            .find(|f| names_match(f, field))
+            .ok_or_else(|| format!("unknown field '{}', expected one of {}", field, FIELDS.join(", ")))?;
+        self.columns.insert(field, column.to_string());
+        Ok(())
+    }
+
+    pub fn column<'a>(&'a self, field: &'a str) -> &'a str {
+        self.columns.get(field).map(String::as_str).unwrap_or(field)
+    }
+}
+
+/// Parses a column mapping given as `FIELD=COLUMN`, e.g. `title=Description`
+pub fn parse_mapping(s: &str) -> Result<(String, String), String> {
+    let (field, column) = s.split_once('=')
+        .ok_or_else(|| format!("invalid mapping '{}', expected FIELD=COLUMN, e.g. title=Description", s))?;
+    Ok((field.trim().to_string(), column.trim().to_string()))
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvOptions {
+    pub columns: ColumnMap,
+    pub timezone: Tz,
+    pub date_format: String,
+    pub tag_separator: char,
+}
+
+/// Reads sessions from CSV with a header row. Rows that can't be read are
+/// collected as errors so the whole file can be checked at once.
+pub fn read(reader: impl Read, options: &CsvOptions) -> Result<ImportBatch, String> {
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(::csv::Trim::All)
+        .from_reader(reader);
+
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let index = |field: &str| {
+        let column = options.columns.column(field);
+        headers.iter().position(|h| names_match(h, column))
+    };
+    let columns: HashMap<&str, usize> = FIELDS.iter()
+        .filter_map(|f| index(f).map(|i| (*f, i)))
+        .collect();
+
+    if !columns.contains_key("category") {
+        return Err(format!("no '{}' column (map it with --map category=COLUMN)", options.columns.column("category")));
+    }
+    if !columns.contains_key("start") && !columns.contains_key("date") {
+        return Err(format!("no '{}' column, or '{}' and '{}' columns (map them with --map start=COLUMN)",
+            options.columns.column("start"), options.columns.column("date"), options.columns.column("time")));
+    }
+
+    let mut batch = ImportBatch::default();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let position = Position::line(e.position().map(|p| p.line() as usize).unwrap_or(0));
+                batch.errors.push(RowError { position, message: e.to_string() });
+                continue;
+            }
+        };
+        let position = Position::line(record.position().map(|p| p.line() as usize).unwrap_or(0));
+        let field = |name: &str| {
+            columns.get(name)
+                .and_then(|i| record.get(*i))
+                .filter(|v| !v.is_empty())
+        };
+
+        match read_row(&field, options) {
+            Ok(mut session) => {
+                session.position = position;
+                batch.sessions.push(session);
+            }
+            Err(message) => batch.errors.push(RowError { position, message }),
+        }
+    }
+
+    Ok(batch)
+}
+
+fn read_row<'r>(field: &dyn Fn(&str) -> Option<&'r str>, options: &CsvOptions) -> Result<ImportedSession, String> {
+    let category = field("category").ok_or("no category")?.to_string();
+
+    let start = match (field("start"), field("date")) {
+        (Some(start), _) => parse_time(start, options)?,
+        (None, Some(date)) => {
+            let text = match field("time") {
+                Some(time) => format!("{} {}", date, time),
+                None => date.to_string(),
+            };
+            parse_time(&text, options)?
+        }
+        (None, None) => return Err("no start time".to_string()),
+    };
+
+    let duration = match (field("duration"), field("end")) {
+        (Some(duration), _) => parse_clock_duration(duration)?,
+        (None, Some(end)) => {
+            // With separate date and time columns, the end is often just a time of day
+            let end = match (parse_time(end, options), field("date")) {
+                (Ok(end), _) => end,
+                (Err(_), Some(date)) => {
+                    let end = parse_time(&format!("{} {}", date, end), options)?;
+                    if end < start { end + chrono::Duration::days(1) } else { end }
+                }
+                (Err(e), None) => return Err(e),
+            };
+            let minutes = (end - start).num_minutes();
+            if minutes < 0 {
+                return Err("ends before it starts".to_string());
+            }
+            u32::try_from(minutes).map_err(|_| "too long for a session".to_string())?
+        }
+        (None, None) => return Err("no duration or end time".to_string()),
+    };
+    if duration == 0 {
+        return Err("duration is zero".to_string());
+    }
+
+    let tags = field("tags")
+        .map(|tags| {
+            tags.split(options.tag_separator)
+                .map(str::trim)
+                .filter(|t| !t.is_empty())
+                .map(str::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+
+    Ok(ImportedSession {
+        position: Position::default(),
+        id: field("id").map(str::to_string),
+        import_key: None,
+        title: field("title").unwrap_or("Untitled").to_string(),
+        category,
+        tags,
+        start,
+        duration,
+    })
+}
+
+/// Reads a time in the configured format and time zone; RFC 3339 timestamps carry their own offset
+fn parse_time(s: &str, options: &CsvOptions) -> Result<DateTime<Utc>, String> {
+    if let Ok(naive) = NaiveDateTime::parse_from_str(s, &options.date_format) {
+        return to_utc(naive, options.timezone);
+    }
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    Err(format!("invalid time '{}', expected format {}", s, options.date_format))
+}
+
+/// Writes sessions as CSV, with times in the configured format and time zone
+pub fn write(writer: impl Write, data: &MetronData, sessions: &[&Session], options: &CsvOptions) -> Result<(), String> {
+    let mut writer = ::csv::Writer::from_writer(writer);
+
+    let headers: Vec<&str> = EXPORT_FIELDS.iter().map(|f| options.columns.column(f)).collect();
+    writer.write_record(&headers).map_err(|e| e.to_string())?;
+
+    let format = |dt: DateTime<Utc>| dt.with_timezone(&options.timezone).format(&options.date_format).to_string();
+    let separator = options.tag_separator.to_string();
+    for session in sessions {
+        let tags: Vec<&str> = session.tag_ids.iter().map(|id| data.tag_name(id)).collect();
+        writer.write_record([
+            session.id.clone(),
+            session.title.clone(),
+            data.category_path(&session.category_id),
+            tags.join(&separator),
+            format(session.start),
+            session.end.map(format).unwrap_or_default(),
+            session.duration.to_string(),
+        ]).map_err(|e| e.to_string())?;
+    }
+
+    writer.flush().map_err(|e| e.to_string())
+}
@@ -0,0 +1,658 @@
+//! iCalendar (RFC 5545) events. Sessions are exported as VEVENTs carrying the
+//! category path and tags in CATEGORIES; events are imported as sessions, with
+//! rules choosing their category.
+
+use super::{import_key, to_utc, ImportBatch, ImportedSession, Position, RowError};
+use crate::models::{MetronData, Session};
+use crate::utils::DateRange;
+use chrono::{DateTime, Datelike, Duration, Months, NaiveDate, NaiveDateTime, Utc, Weekday};
+use chrono_tz::Tz;
+use std::io::Write;
+
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+/// UIDs of exported sessions end in this, so importing them again finds the session
+const UID_DOMAIN: &str = "@metron";
+
+/// Occurrences expanded from a single recurring event at most
+const MAX_OCCURRENCES: usize = 10_000;
+
+/// Writes sessions as a calendar with one event each
+pub fn write(out: &mut dyn Write, data: &MetronData, sessions: &[&Session]) -> Result<(), String> {
+    let now = Utc::now().format(TIME_FORMAT).to_string();
+    let mut lines = vec![
+        "BEGIN:VCALENDAR".to_string(),
+        "VERSION:2.0".to_string(),
+        format!("PRODID:-//metron//metron {}//EN", env!("CARGO_PKG_VERSION")),
+        "CALSCALE:GREGORIAN".to_string(),
+        "X-WR-CALNAME:metron".to_string(),
+    ];
+    for session in sessions {
+        let category = data.category_path(&session.category_id);
+        let tags: Vec<&str> = session.tag_ids.iter().map(|id| data.tag_name(id)).collect();
+        let end = session.start + Duration::minutes(session.duration as i64);
+
+        let mut categories = vec![escape(&category)];
+        categories.extend(tags.iter().map(|t| escape(t)));
+        let mut description = format!("Category: {}", category);
+        if !tags.is_empty() {
+            description.push_str(&format!("\nTags: {}", tags.join(", ")));
+        }
+
+        lines.extend([
+            "BEGIN:VEVENT".to_string(),
+            format!("UID:{}{}", session.id, UID_DOMAIN),
+            format!("DTSTAMP:{}", now),
+            format!("DTSTART:{}", session.start.format(TIME_FORMAT)),
+            format!("DTEND:{}", end.format(TIME_FORMAT)),
+            format!("SUMMARY:{}", escape(&session.title)),
+            format!("CATEGORIES:{}", categories.join(",")),
+            format!("DESCRIPTION:{}", escape(&description)),
+            "END:VEVENT".to_string(),
+        ]);
+    }
+    lines.push("END:VCALENDAR".to_string());
+
+    for line in lines {
+        write!(out, "{}\r\n", fold(&line)).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut chars = text.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            result.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n') | Some('N') => result.push('\n'),
+            Some(c) => result.push(c),
+            None => {}
+        }
+    }
+    result
+}
+
+/// Folds lines longer than 75 octets, without splitting characters
+fn fold(line: &str) -> String {
+    let mut folded = String::with_capacity(line.len());
+    let mut octets = 0;
+    for c in line.chars() {
+        if octets + c.len_utf8() > 75 {
+            folded.push_str("\r\n ");
+            octets = 1;
+        }
+        folded.push(c);
+        octets += c.len_utf8();
+    }
+    folded
+}
+
+/// How events become sessions
+#[derive(Debug, Clone)]
+pub struct IcsOptions {
+    /// Time zone for times without one
+    pub timezone: Tz,
+    /// (text, category): events whose title contains the text, ignoring case
+    pub title_rules: Vec<(String, String)>,
+    /// (calendar name, category): all events of the calendar
+    pub calendar_rules: Vec<(String, String)>,
+    /// Category for events no rule matches
+    pub default_category: Option<String>,
+}
+
+/// Parses a rule given as `TEXT=CATEGORY`, e.g. `standup=Internal/Meetings`
+pub fn parse_rule(s: &str) -> Result<(String, String), String> {
+    let (text, category) = s.rsplit_once('=')
+        .filter(|(text, category)| !text.trim().is_empty() && !category.trim().is_empty())
+        .ok_or_else(|| format!("invalid rule '{}', expected TEXT=CATEGORY, e.g. standup=Internal", s))?;
+    Ok((text.trim().to_string(), category.trim().to_string()))
+}
+
+/// A content line: `NAME;PARAM=VALUE:value`
+struct Property {
+    name: String,
+    params: Vec<(String, String)>,
+    value: String,
+}
+
+impl Property {
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+    }
+}
+
+fn parse_property(line: &str) -> Option<Property> {
+    // The value starts at the first colon outside a quoted parameter value
+    let mut quoted = false;
+    let colon = line.char_indices().find(|(_, c)| {
+        if *c == '"' {
+            quoted = !quoted;
+        }
+        *c == ':' && !quoted
+    })?.0;
+
+    let mut parts = line[..colon].split(';');
+    let name = parts.next()?.to_uppercase();
+    let params = parts
+        .filter_map(|p| p.split_once('='))
+        .map(|(n, v)| (n.to_uppercase(), v.trim_matches('"').to_string()))
+        .collect();
+    Some(Property { name, params, value: line[colon + 1..].to_string() })
+}
+
+/// Joins folded lines, remembering the line each one started on
+fn unfold(contents: &str) -> Vec<(usize, String)> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for (index, line) in contents.lines().enumerate() {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(rest), Some((_, last))) => last.push_str(rest),
+            _ => lines.push((index + 1, line.to_string())),
+        }
+    }
+    lines
+}
+
+struct Event {
+    line: usize,
+    properties: Vec<Property>,
+}
+
+impl Event {
+    fn get(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    fn all(&self, name: &str) -> impl Iterator<Item = &Property> + '_ {
+        let name = name.to_string();
+        self.properties.iter().filter(move |p| p.name == name)
+    }
+}
+
+/// Reads the events of a calendar that started in `range` and before `now`.
+/// Recurring events are expanded; all-day and cancelled events are ignored.
+pub fn read(contents: &str, options: &IcsOptions, range: &DateRange, now: DateTime<Utc>) -> Result<ImportBatch, String> {
+    let lines = unfold(contents);
+    if !lines.iter().any(|(_, l)| l.trim().eq_ignore_ascii_case("BEGIN:VCALENDAR")) {
+        return Err("not an iCalendar file (no BEGIN:VCALENDAR)".to_string());
+    }
+
+    let mut calendar: Option<String> = None;
+    let mut events: Vec<Event> = Vec::new();
+    let mut components: Vec<String> = Vec::new();
+    for (number, line) in &lines {
+        let Some(property) = parse_property(line.trim_end()) else { continue };
+        match property.name.as_str() {
+            "BEGIN" => {
+                let component = property.value.to_uppercase();
+                if component == "VEVENT" && components.len() == 1 {
+                    events.push(Event { line: *number, properties: Vec::new() });
+                }
+                components.push(component);
+            }
+            "END" => {
+                components.pop();
+            }
+            "X-WR-CALNAME" if components.len() == 1 => calendar = Some(unescape(&property.value)),
+            _ if components.len() == 2 && components[1] == "VEVENT" => {
+                if let Some(event) = events.last_mut() {
+                    event.properties.push(property);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    // Changed occurrences of recurring events come as events of their own with a RECURRENCE-ID
+    let overridden: Vec<(String, DateTime<Utc>)> = events.iter()
+        .filter_map(|event| {
+            let uid = event.get("UID")?.value.clone();
+            let start = parse_time(event.get("RECURRENCE-ID")?, options.timezone).ok()?;
+            Some((uid, start))
+        })
+        .collect();
+
+    let mut batch = ImportBatch::default();
+    for event in &events {
+        let position = Position::line(event.line);
+        match read_event(event, calendar.as_deref(), &overridden, options, range, now) {
+            Ok(Some(sessions)) => {
+                batch.sessions.extend(sessions.into_iter().map(|s| ImportedSession { position: position.clone(), ..s }));
+            }
+            Ok(None) => batch.ignored += 1,
+            Err(message) => batch.errors.push(RowError { position, message }),
+        }
+    }
+    Ok(batch)
+}
+
+/// The sessions of an event, or `None` for events that aren't tracked time
+fn read_event(
+    event: &Event,
+    calendar: Option<&str>,
+    overridden: &[(String, DateTime<Utc>)],
+    options: &IcsOptions,
+    range: &DateRange,
+    now: DateTime<Utc>,
+) -> Result<Option<Vec<ImportedSession>>, String> {
+    let cancelled = event.get("STATUS").is_some_and(|s| s.value.eq_ignore_ascii_case("CANCELLED"));
+    let start = event.get("DTSTART").ok_or("no start time")?;
+    if cancelled || start.param("VALUE").is_some_and(|v| v.eq_ignore_ascii_case("DATE")) {
+        return Ok(None);
+    }
+
+    let title = event.get("SUMMARY").map(|p| unescape(&p.value)).unwrap_or_else(|| "Untitled".to_string());
+    let uid = event.get("UID").map(|p| p.value.clone()).unwrap_or_default();
+    let first = parse_time(start, options.timezone)?;
+    let duration = match (event.get("DTEND"), event.get("DURATION")) {
+        (Some(end), _) => {
+            let minutes = (parse_time(end, options.timezone)? - first).num_minutes();
+            u32::try_from(minutes).map_err(|_| "ends before it starts".to_string())?
+        }
+        (None, Some(duration)) => parse_duration(&duration.value)?,
+        (None, None) => return Ok(None),
+    };
+    if duration == 0 {
+        return Ok(None);
+    }
+
+    let categories: Vec<String> = event.all("CATEGORIES")
+        .flat_map(|p| split_list(&p.value))
+        .collect();
+    let (category, tags) = choose_category(&title, calendar, &categories, options)?;
+
+    let starts = match event.get("RRULE") {
+        Some(rule) if event.get("RECURRENCE-ID").is_none() => {
+            let exceptions: Vec<DateTime<Utc>> = event.all("EXDATE")
+                .flat_map(|p| p.value.split(',').filter_map(|v| parse_value(v, p.param("TZID"), options.timezone).ok()))
+                .collect();
+            expand(&rule.value, start, options.timezone, now)?
+                .into_iter()
+                .filter(|s| !exceptions.contains(s) && !overridden.iter().any(|(u, o)| *u == uid && o == s))
+                .collect()
+        }
+        _ => vec![first],
+    };
+
+    // Sessions exported by metron keep their ID
+    let id = uid.strip_suffix(UID_DOMAIN).filter(|_| starts.len() == 1).map(str::to_string);
+    let sessions = starts.into_iter()
+        .filter(|s| *s <= now && range.contains(*s, now))
+        .map(|start| ImportedSession {
+            position: Position::default(),
+            id: id.clone(),
+            import_key: Some(import_key("ics", &[uid.as_str(), start.to_rfc3339().as_str()])),
+            title: title.clone(),
+            category: category.clone(),
+            tags: tags.clone(),
+            start,
+            duration,
+        })
+        .collect();
+    Ok(Some(sessions))
+}
+
+/// Title rules come first, then calendar rules. Without a match, the event's
+/// first CATEGORIES entry is the category and the others are tags, which is
+/// how metron exports sessions.
+fn choose_category(
+    title: &str,
+    calendar: Option<&str>,
+    categories: &[String],
+    options: &IcsOptions,
+) -> Result<(String, Vec<String>), String> {
+    let title_lower = title.to_lowercase();
+    if let Some((_, category)) = options.title_rules.iter().find(|(text, _)| title_lower.contains(&text.to_lowercase())) {
+        return Ok((category.clone(), Vec::new()));
+    }
+    if let Some((_, category)) = calendar.and_then(|c| options.calendar_rules.iter().find(|(name, _)| name.eq_ignore_ascii_case(c))) {
+        return Ok((category.clone(), Vec::new()));
+    }
+    if let Some((category, tags)) = categories.split_first() {
+        return Ok((category.clone(), tags.to_vec()));
+    }
+    match &options.default_category {
+        Some(category) => Ok((category.clone(), Vec::new())),
+        None => Err(format!("no rule matches '{}' (see --map-title, --map-calendar and --default-category)", title)),
+    }
+}
+
+/// Splits a comma-separated list, respecting escaped commas
+fn split_list(value: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {
+                current.push(c);
+                current.extend(chars.next());
+            }
+            ',' => items.push(std::mem::take(&mut current)),
+            c => current.push(c),
+        }
+    }
+    items.push(current);
+    items.iter().map(|i| unescape(i.trim())).filter(|i| !i.is_empty()).collect()
+}
+
+/// Reads a DATE-TIME in UTC (`Z`), in its TZID, or floating in `timezone`.
+/// TZIDs that aren't IANA names, like Outlook's, are read in `timezone` too.
+fn parse_time(property: &Property, timezone: Tz) -> Result<DateTime<Utc>, String> {
+    parse_value(&property.value, property.param("TZID"), timezone)
+}
+
+fn parse_value(value: &str, tzid: Option<&str>, timezone: Tz) -> Result<DateTime<Utc>, String> {
+    let value = value.trim();
+    if let Ok(naive) = NaiveDateTime::parse_from_str(value, TIME_FORMAT) {
+        return Ok(naive.and_utc());
+    }
+    let tz = tzid.and_then(|id| id.parse::<Tz>().ok()).unwrap_or(timezone);
+    NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S")
+        .or_else(|_| NaiveDate::parse_from_str(value, "%Y%m%d").map(|d| d.and_time(chrono::NaiveTime::MIN)))
+        .map_err(|_| format!("invalid time '{}'", value))
+        .and_then(|naive| to_utc(naive, tz))
+}
+
+/// Parses a DURATION like `PT1H30M` or `P1DT2H` into minutes
+fn parse_duration(value: &str) -> Result<u32, String> {
+    let invalid = || format!("invalid duration '{}'", value);
+    let rest = value.trim().strip_prefix('P').ok_or_else(invalid)?;
+    let mut minutes: u32 = 0;
+    let mut number = String::new();
+    let mut in_time = false;
+    for c in rest.chars() {
+        match c {
+            'T' => in_time = true,
+            '0'..='9' => number.push(c),
+            unit => {
+                let n: u32 = number.parse().map_err(|_| invalid())?;
+                number.clear();
+                let part = match (unit, in_time) {
+                    ('W', false) => n.checked_mul(7 * 24 * 60),
+                    ('D', false) => n.checked_mul(24 * 60),
+                    ('H', true) => n.checked_mul(60),
+                    ('M', true) => Some(n),
+                    ('S', true) => Some(n / 60 + u32::from(n % 60 >= 30)),
+                    _ => return Err(invalid()),
+                };
+                minutes = part.and_then(|p| p.checked_add(minutes)).ok_or_else(invalid)?;
+            }
+        }
+    }
+    if !number.is_empty() {
+        return Err(invalid());
+    }
+    Ok(minutes)
+}
+
+/// Start times of a recurring event up to `until`. Supports the common rules:
+/// FREQ=DAILY, WEEKLY (with BYDAY), MONTHLY and YEARLY with INTERVAL, COUNT and UNTIL.
+/// Occurrences keep their local time across DST changes.
+fn expand(rule: &str, start: &Property, timezone: Tz, until: DateTime<Utc>) -> Result<Vec<DateTime<Utc>>, String> {
+    let mut freq = None;
+    let mut interval = 1u32;
+    let mut count = None;
+    let mut rule_until = None;
+    let mut by_day: Vec<Weekday> = Vec::new();
+    for part in rule.split(';') {
+        let Some((key, value)) = part.split_once('=') else { continue };
+        match key.to_uppercase().as_str() {
+            "FREQ" => freq = Some(value.to_uppercase()),
+            "INTERVAL" => interval = value.parse().map_err(|_| format!("invalid INTERVAL in '{}'", rule))?,
+            "COUNT" => count = Some(value.parse::<usize>().map_err(|_| format!("invalid COUNT in '{}'", rule))?),
+            "UNTIL" => rule_until = Some(parse_value(value, start.param("TZID"), timezone)?),
+            "BYDAY" => {
+                for day in value.split(',') {
+                    by_day.push(parse_weekday(day).ok_or_else(|| format!("unsupported BYDAY '{}'", day))?);
+                }
+            }
+            "WKST" => {}
+            other => return Err(format!("recurrence rule part {} is not supported", other)),
+        }
+    }
+    let interval = interval.max(1);
+
+    let tz = start.param("TZID").and_then(|id| id.parse::<Tz>().ok()).unwrap_or(timezone);
+    let first = parse_time(start, timezone)?;
+    // Recur in local time so a 09:00 meeting stays at 09:00 after a DST change
+    let local = if start.value.trim().ends_with('Z') {
+        first.naive_utc()
+    } else {
+        first.with_timezone(&tz).naive_local()
+    };
+    let to_instant = |naive: NaiveDateTime| {
+        if start.value.trim().ends_with('Z') { Ok(naive.and_utc()) } else { to_utc(naive, tz) }
+    };
+    let until = rule_until.map_or(until, |u| u.min(until));
+
+    let mut starts = Vec::new();
+    let mut period = 0u32;
+    loop {
+        // Steps beyond the calendar's range end the recurrence
+        let Some(step) = period.checked_mul(interval) else { return Ok(starts) };
+        let candidates: Vec<NaiveDateTime> = match freq.as_deref() {
+            Some("DAILY") => local.checked_add_signed(Duration::days(step as i64)).into_iter().collect(),
+            Some("WEEKLY") if by_day.is_empty() => local.checked_add_signed(Duration::weeks(step as i64)).into_iter().collect(),
+            Some("WEEKLY") => {
+                let monday = local - Duration::days(local.weekday().num_days_from_monday() as i64);
+                let Some(week_start) = monday.checked_add_signed(Duration::weeks(step as i64)) else { return Ok(starts) };
+                let mut days: Vec<NaiveDateTime> = by_day.iter()
+                    .filter_map(|d| week_start.checked_add_signed(Duration::days(d.num_days_from_monday() as i64)))
+                    .filter(|d| *d >= local)
+                    .collect();
+                days.sort();
+                days
+            }
+            // Months without the day (like the 31st) are skipped
+            Some("MONTHLY") => local.checked_add_months(Months::new(step))
+                .filter(|d| d.day() == local.day())
+                .into_iter()
+                .collect(),
+            Some("YEARLY") => step.checked_mul(12)
+                .and_then(|months| local.checked_add_months(Months::new(months)))
+                .filter(|d| d.day() == local.day())
+                .into_iter()
+                .collect(),
+            _ => return Err(format!("unsupported recurrence rule '{}'", rule)),
+        };
+
+        for candidate in candidates {
+            let Ok(instant) = to_instant(candidate) else { continue };
+            if instant > until || count.is_some_and(|c| starts.len() >= c) || starts.len() >= MAX_OCCURRENCES {
+                return Ok(starts);
+            }
+            starts.push(instant);
+        }
+        period += 1;
+        if period as usize > MAX_OCCURRENCES {
+            return Ok(starts);
+        }
+    }
+}
+
+fn parse_weekday(day: &str) -> Option<Weekday> {
+    match day.trim().to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn utc(s: &str) -> DateTime<Utc> {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M").unwrap().and_utc()
+    }
+
+    fn dtstart(value: &str, tzid: Option<&str>) -> Property {
+        Property {
+            name: "DTSTART".to_string(),
+            params: tzid.map(|t| ("TZID".to_string(), t.to_string())).into_iter().collect(),
+            value: value.to_string(),
+        }
+    }
+
+    fn expand_utc(rule: &str, start: &str, until: &str) -> Vec<DateTime<Utc>> {
+        expand(rule, &dtstart(start, None), Tz::UTC, utc(until)).unwrap()
+    }
+
+    fn options() -> IcsOptions {
+        IcsOptions {
+            timezone: Tz::UTC,
+            title_rules: Vec::new(),
+            calendar_rules: Vec::new(),
+            default_category: Some("Meetings".to_string()),
+        }
+    }
+
+    fn all_time() -> DateRange {
+        DateRange { period: None, from: None, to: None }
+    }
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("PT1H30M"), Ok(90));
+        assert_eq!(parse_duration("P1DT2H"), Ok(26 * 60));
+        assert_eq!(parse_duration("P1W"), Ok(7 * 24 * 60));
+        assert_eq!(parse_duration("PT90S"), Ok(2));
+        assert_eq!(parse_duration("PT29S"), Ok(0));
+    }
+
+    #[test]
+    fn rejects_malformed_durations() {
+        assert!(parse_duration("1H").is_err());
+        assert!(parse_duration("P1H").is_err());
+        assert!(parse_duration("PT15").is_err());
+        assert!(parse_duration("PTH").is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_durations() {
+        assert!(parse_duration("P99999999D").is_err());
+        assert!(parse_duration("P4294967295W").is_err());
+        assert!(parse_duration("PT4294967295M1H").is_err());
+    }
+
+    #[test]
+    fn expands_daily_with_count() {
+        let starts = expand_utc("FREQ=DAILY;COUNT=3", "20260105T090000Z", "2026-12-31 00:00");
+        assert_eq!(starts, vec![utc("2026-01-05 09:00"), utc("2026-01-06 09:00"), utc("2026-01-07 09:00")]);
+    }
+
+    #[test]
+    fn expands_with_interval_up_to_until() {
+        let starts = expand_utc("FREQ=DAILY;INTERVAL=2;UNTIL=20260110T235959Z", "20260105T090000Z", "2026-12-31 00:00");
+        assert_eq!(starts, vec![utc("2026-01-05 09:00"), utc("2026-01-07 09:00"), utc("2026-01-09 09:00")]);
+    }
+
+    #[test]
+    fn stops_at_until() {
+        let starts = expand_utc("FREQ=DAILY", "20260105T090000Z", "2026-01-08 08:00");
+        assert_eq!(starts.len(), 3);
+    }
+
+    #[test]
+    fn stops_unbounded_rules_at_max_occurrences() {
+        let starts = expand_utc("FREQ=DAILY", "20000101T090000Z", "2099-12-31 00:00");
+        assert_eq!(starts.len(), MAX_OCCURRENCES);
+        assert_eq!(starts.last(), Some(&(utc("2000-01-01 09:00") + Duration::days(MAX_OCCURRENCES as i64 - 1))));
+    }
+
+    #[test]
+    fn expands_weekly_by_day() {
+        // Starts on a Wednesday, so Monday of the first week is skipped
+        let starts = expand_utc("FREQ=WEEKLY;BYDAY=MO,WE,FR;COUNT=4", "20260107T100000Z", "2026-12-31 00:00");
+        assert_eq!(starts, vec![
+            utc("2026-01-07 10:00"),
+            utc("2026-01-09 10:00"),
+            utc("2026-01-12 10:00"),
+            utc("2026-01-14 10:00"),
+        ]);
+    }
+
+    #[test]
+    fn keeps_local_time_across_dst() {
+        // Europe/Berlin switches to summer time on 2026-03-29
+        let start = dtstart("20260323T090000", Some("Europe/Berlin"));
+        let starts = expand("FREQ=WEEKLY;COUNT=2", &start, Tz::UTC, utc("2026-12-31 00:00")).unwrap();
+        assert_eq!(starts, vec![utc("2026-03-23 08:00"), utc("2026-03-30 07:00")]);
+    }
+
+    #[test]
+    fn skips_months_without_the_day() {
+        let starts = expand_utc("FREQ=MONTHLY;COUNT=3", "20260131T090000Z", "2026-12-31 00:00");
+        assert_eq!(starts, vec![utc("2026-01-31 09:00"), utc("2026-03-31 09:00"), utc("2026-05-31 09:00")]);
+    }
+
+    #[test]
+    fn survives_huge_intervals() {
+        let starts = expand_utc("FREQ=YEARLY;INTERVAL=4294967295", "20260105T090000Z", "2026-12-31 00:00");
+        assert_eq!(starts, vec![utc("2026-01-05 09:00")]);
+    }
+
+    #[test]
+    fn rejects_unsupported_rules() {
+        let start = dtstart("20260105T090000Z", None);
+        assert!(expand("FREQ=DAILY;BYMONTH=1", &start, Tz::UTC, utc("2026-12-31 00:00")).is_err());
+        assert!(expand("FREQ=HOURLY", &start, Tz::UTC, utc("2026-12-31 00:00")).is_err());
+        assert!(expand("FREQ=WEEKLY;BYDAY=1MO", &start, Tz::UTC, utc("2026-12-31 00:00")).is_err());
+    }
+
+    #[test]
+    fn applies_exdate_and_moved_occurrences() {
+        let calendar = "BEGIN:VCALENDAR\r
+BEGIN:VEVENT\r
+UID:standup\r
+SUMMARY:Standup\r
+DTSTART:20260105T090000Z\r
+DTEND:20260105T091500Z\r
+RRULE:FREQ=DAILY;COUNT=4\r
+EXDATE:20260106T090000Z\r
+END:VEVENT\r
+BEGIN:VEVENT\r
+UID:standup\r
+SUMMARY:Standup (moved)\r
+RECURRENCE-ID:20260107T090000Z\r
+DTSTART:20260107T140000Z\r
+DTEND:20260107T143000Z\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+        let batch = read(calendar, &options(), &all_time(), utc("2026-12-31 00:00")).unwrap();
+        let sessions: Vec<(DateTime<Utc>, &str, u32)> = batch.sessions.iter()
+            .map(|s| (s.start, s.title.as_str(), s.duration))
+            .collect();
+        assert_eq!(sessions, vec![
+            (utc("2026-01-05 09:00"), "Standup", 15),
+            (utc("2026-01-08 09:00"), "Standup", 15),
+            (utc("2026-01-07 14:00"), "Standup (moved)", 30),
+        ]);
+        assert!(batch.errors.is_empty());
+    }
+
+    #[test]
+    fn reports_overflowing_durations_as_row_errors() {
+        let calendar = "BEGIN:VCALENDAR\nBEGIN:VEVENT\nUID:x\nDTSTART:20260105T090000Z\nDURATION:P99999999D\nEND:VEVENT\nEND:VCALENDAR\n";
+        let batch = read(calendar, &options(), &all_time(), utc("2026-12-31 00:00")).unwrap();
+        assert!(batch.sessions.is_empty());
+        assert_eq!(batch.errors.len(), 1);
+    }
+}
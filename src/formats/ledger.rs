@@ -0,0 +1,56 @@
+//! The timeclock format read by ledger and hledger: a check-in line with the
+//! account and description, and a check-out line.
+//!
+//! ```text
+//! i 2026/09/01 09:00:00 Acme:Website  Homepage mockups  ; design:, client:
+//! o 2026/09/01 10:30:00
+//! ```
+
+use crate::models::{MetronData, Session};
+use chrono::Duration;
+use chrono_tz::Tz;
+use std::io::Write;
+
+const TIME_FORMAT: &str = "%Y/%m/%d %H:%M:%S";
+
+/// Writes a check-in and check-out per session. The category path is the
+/// account, the title the description and tags become hledger tags in a comment.
+pub fn write(out: &mut dyn Write, data: &MetronData, sessions: &[&Session], timezone: Tz) -> Result<(), String> {
+    for session in sessions {
+        let start = session.start.with_timezone(&timezone);
+        let end = start + Duration::minutes(session.duration as i64);
+        let account = account(&data.category_path(&session.category_id));
+
+        let mut line = format!("i {} {}  {}", start.format(TIME_FORMAT), account, single_line(&session.title));
+        if !session.tag_ids.is_empty() {
+            let tags: Vec<String> = session.tag_ids.iter()
+                .map(|id| format!("{}:", tag_name(data.tag_name(id))))
+                .collect();
+            line.push_str(&format!("  ; {}", tags.join(", ")));
+        }
+        writeln!(out, "{}", line)
+            .and_then(|_| writeln!(out, "o {}", end.format(TIME_FORMAT)))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Subcategories become subaccounts. Two spaces would end an account name, so
+/// whitespace is collapsed.
+fn account(path: &str) -> String {
+    path.split('/')
+        .map(|segment| segment.split_whitespace().collect::<Vec<_>>().join(" ").replace(':', "-"))
+        .collect::<Vec<_>>()
+        .join(":")
+}
+
+fn single_line(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").replace(';', ",")
+}
+
+/// hledger tag names can't contain spaces, commas or colons
+fn tag_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_whitespace() || c == ',' || c == ':' { '-' } else { c })
+        .collect()
+}
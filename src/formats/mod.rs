@@ -0,0 +1,145 @@
+//! Converters between metron sessions and the file formats of other tools.
+//! Readers produce `ImportedSession`s that still name their category and tags;
+//! `ImportManager` resolves those against the data file.
+
+pub mod csv;
+pub mod ics;
+pub mod ledger;
+pub mod org;
+pub mod timewarrior;
+pub mod trackers;
+pub mod watson;
+
+use chrono::{DateTime, LocalResult, NaiveDateTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// A session read from a file, before its category and tags are looked up
+#[derive(Debug, Clone)]
+pub struct ImportedSession {
+    pub position: Position, // where it came from, for error messages
+    pub id: Option<String>,
+    pub import_key: Option<String>, // for formats without IDs, see `import_key`
+    pub title: String,
+    pub category: String, // path like Acme/Website
+    pub tags: Vec<String>,
+    pub start: DateTime<Utc>,
+    pub duration: u32, // in minutes
+}
+
+/// Where a record was read: a line, or an entry for formats like JSON
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Position {
+    pub file: Option<String>, // for sources made of several files
+    pub number: usize,
+    pub entry: bool,
+}
+
+impl Position {
+    pub fn line(number: usize) -> Self {
+        Self { file: None, number, entry: false }
+    }
+
+    pub fn entry(number: usize) -> Self {
+        Self { file: None, number, entry: true }
+    }
+
+    pub fn in_file(self, file: &str) -> Self {
+        Self { file: Some(file.to_string()), ..self }
+    }
+}
+
+impl std::fmt::Display for Position {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let unit = if self.entry { "entry" } else { "line" };
+        match &self.file {
+            Some(file) => write!(f, "{} {} {}", file, unit, self.number),
+            None if self.entry => write!(f, "Entry {}", self.number),
+            None => write!(f, "Line {}", self.number),
+        }
+    }
+}
+
+/// A record that could not be read
+#[derive(Debug, Clone)]
+pub struct RowError {
+    pub position: Position,
+    pub message: String,
+}
+
+/// Everything read from one file
+#[derive(Debug, Default)]
+pub struct ImportBatch {
+    pub sessions: Vec<ImportedSession>,
+    pub errors: Vec<RowError>,
+    pub ignored: usize, // records that aren't tracked time, like all-day events
+}
+
+/// Interprets a local time in `tz`; times skipped by a DST change are rejected,
+/// repeated ones use the earlier instant
+pub fn to_utc(naive: NaiveDateTime, tz: Tz) -> Result<DateTime<Utc>, String> {
+    match tz.from_local_datetime(&naive) {
+        LocalResult::Single(dt) => Ok(dt.with_timezone(&Utc)),
+        LocalResult::Ambiguous(earlier, _) => Ok(earlier.with_timezone(&Utc)),
+        LocalResult::None => Err(format!("{} does not exist in {}", naive, tz)),
+    }
+}
+
+/// Parses durations as written by spreadsheets and other trackers: `H:MM`,
+/// `H:MM:SS` (seconds are rounded), or anything `utils::parse_minutes` accepts
+pub fn parse_clock_duration(s: &str) -> Result<u32, String> {
+    let s = s.trim();
+    if !s.contains(':') {
+        return crate::utils::parse_minutes(s);
+    }
+
+    let invalid = || format!("invalid duration '{}', expected H:MM or H:MM:SS", s);
+    let parts: Vec<u32> = s.split(':')
+        .map(|p| p.parse::<u32>().map_err(|_| invalid()))
+        .collect::<Result<_, _>>()?;
+    let minutes = match parts.as_slice() {
+        [h, m] => h.checked_mul(60).and_then(|h| h.checked_add(*m)),
+        [h, m, sec] => h.checked_mul(60)
+            .and_then(|h| h.checked_add(*m))
+            .and_then(|minutes| minutes.checked_add(sec / 60 + u32::from(sec % 60 >= 30))),
+        _ => None,
+    };
+    minutes.ok_or_else(invalid)
+}
+
+/// Identifies a record of another tracker across imports, from the fields that
+/// describe it. FNV-1a is used because, unlike std's hasher, it never changes.
+pub fn import_key(source: &str, fields: &[&str]) -> String {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in fields.join("\u{1f}").bytes() {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    format!("{}:{:016x}", source, hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_clock_durations() {
+        assert_eq!(parse_clock_duration("1:30"), Ok(90));
+        assert_eq!(parse_clock_duration("0:45:30"), Ok(46));
+        assert_eq!(parse_clock_duration("0:45:29"), Ok(45));
+        assert_eq!(parse_clock_duration("90m"), Ok(90));
+    }
+
+    #[test]
+    fn rejects_malformed_clock_durations() {
+        assert!(parse_clock_duration("1:").is_err());
+        assert!(parse_clock_duration("1:2:3:4").is_err());
+        assert!(parse_clock_duration("-1:30").is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_clock_durations() {
+        assert!(parse_clock_duration("99999999:00").is_err());
+        assert!(parse_clock_duration("71582788:16").is_err());
+        assert!(parse_clock_duration("71582788:15:30").is_err());
+    }
+}
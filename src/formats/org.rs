@@ -0,0 +1,93 @@
+//! org-mode headlines with `CLOCK:` entries. Categories become headlines
+//! nested like the category tree; below each, sessions with the same title
+//! and tags are clocked into one task headline.
+//!
+//! ```text
+//! * Acme
+//! ** Website
+//! *** Homepage mockups                                          :design:
+//!     :LOGBOOK:
+//!     CLOCK: [2026-09-01 Tue 09:00]--[2026-09-01 Tue 10:30] =>  1:30
+//!     :END:
+//! ```
+
+use crate::models::{MetronData, Session};
+use chrono::Duration;
+use chrono_tz::Tz;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io::Write;
+
+const TIME_FORMAT: &str = "%Y-%m-%d %a %H:%M";
+
+pub fn write(out: &mut dyn Write, data: &MetronData, sessions: &[&Session], timezone: Tz) -> Result<(), String> {
+    let mut by_category: HashMap<&str, Vec<&Session>> = HashMap::new();
+    for session in sessions {
+        by_category.entry(session.category_id.as_str()).or_default().push(session);
+    }
+
+    // Categories with sessions and their parents, in tree order; then IDs that match no category
+    let mut shown: HashSet<String> = HashSet::new();
+    for id in by_category.keys() {
+        shown.extend(data.ancestors(id).into_iter().map(|c| c.id.clone()));
+    }
+    let mut headlines: Vec<(String, String, usize)> = data.category_tree().into_iter()
+        .filter(|(c, _)| shown.contains(&c.id))
+        .map(|(c, depth)| (c.id.clone(), c.name.clone(), depth))
+        .collect();
+    let mut dangling: Vec<&str> = by_category.keys().copied().filter(|id| data.category(id).is_none()).collect();
+    dangling.sort();
+    headlines.extend(dangling.into_iter().map(|id| (id.to_string(), id.to_string(), 0)));
+
+    let mut lines: Vec<String> = Vec::new();
+    for (id, name, depth) in headlines {
+        lines.push(format!("{} {}", "*".repeat(depth + 1), name));
+        let Some(sessions) = by_category.get(id.as_str()) else { continue };
+
+        // One task per title and set of tags, in the order first clocked
+        let mut tasks: BTreeMap<(usize, String, Vec<String>), Vec<&Session>> = BTreeMap::new();
+        let mut first_seen: HashMap<(String, Vec<String>), usize> = HashMap::new();
+        for session in sessions {
+            let tags: Vec<String> = session.tag_ids.iter().map(|t| tag_name(data.tag_name(t))).collect();
+            let key = (session.title.clone(), tags);
+            let order = first_seen.len();
+            let order = *first_seen.entry(key.clone()).or_insert(order);
+            tasks.entry((order, key.0, key.1)).or_default().push(session);
+        }
+
+        let stars = "*".repeat(depth + 2);
+        let indent = " ".repeat(depth + 3);
+        for ((_, title, tags), sessions) in tasks {
+            let headline = format!("{} {}", stars, title.split_whitespace().collect::<Vec<_>>().join(" "));
+            if tags.is_empty() {
+                lines.push(headline);
+            } else {
+                // Tags are aligned to the right like org-mode does by default
+                let tags = format!(":{}:", tags.join(":"));
+                let padding = 77usize.saturating_sub(headline.chars().count() + tags.len()).max(1);
+                lines.push(format!("{}{}{}", headline, " ".repeat(padding), tags));
+            }
+            lines.push(format!("{}:LOGBOOK:", indent));
+            // org-mode lists the latest clock first
+            for session in sessions.iter().rev() {
+                let start = session.start.with_timezone(&timezone);
+                let end = start + Duration::minutes(session.duration as i64);
+                lines.push(format!("{}CLOCK: [{}]--[{}] => {:>2}:{:02}",
+                    indent, start.format(TIME_FORMAT), end.format(TIME_FORMAT),
+                    session.duration / 60, session.duration % 60));
+            }
+            lines.push(format!("{}:END:", indent));
+        }
+    }
+
+    for line in lines {
+        writeln!(out, "{}", line).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// org tags may only contain letters, numbers, `_`, `@`, `#` and `%`
+fn tag_name(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_alphanumeric() || "_@#%".contains(c) { c } else { '_' })
+        .collect()
+}
@@ -0,0 +1,274 @@
+//! Timewarrior's interval files (`data/YYYY-MM.data`). Each line is an interval:
+//!
+//! ```text
+//! inc 20260901T090000Z - 20260901T103000Z # "Acme/Website" design # "Homepage mockups"
+//! ```
+//!
+//! Timewarrior only knows tags, and keeps them sorted (`timew start design Acme`
+//! is stored as `# Acme design`). The category is the tag a `--map-tag` rule
+//! names, else the tag naming an existing category, else the only tag. Files
+//! metron wrote keep the category first, so lines whose tags are out of order
+//! take the first tag. The annotation is the title.
+
+use super::{import_key, ImportBatch, ImportedSession, Position, RowError};
+use crate::models::{names_match, MetronData, Session};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::Path;
+
+const TIME_FORMAT: &str = "%Y%m%dT%H%M%SZ";
+
+#[derive(Debug, Clone, Default)]
+pub struct TimewarriorOptions {
+    /// Category for intervals without tags, or whose tags name no category
+    pub default_category: Option<String>,
+    /// (tag, category): intervals with the tag, ignoring case
+    pub tag_rules: Vec<(String, String)>,
+    /// Paths of the existing categories, to tell which tag is the category
+    pub categories: Vec<String>,
+}
+
+/// Reads a Timewarrior data directory (or the `.timewarrior` directory above
+/// it), or a single `.data` file
+pub fn read_path(path: &Path, options: &TimewarriorOptions) -> Result<ImportBatch, String> {
+    let read_file = |file: &Path| std::fs::read_to_string(file).map_err(|e| format!("{}: {}", file.display(), e));
+    if path.is_file() {
+        return Ok(read(&read_file(path)?, None, options));
+    }
+
+    let dir = if path.join("data").is_dir() { path.join("data") } else { path.to_path_buf() };
+    let mut files: Vec<_> = std::fs::read_dir(&dir)
+        .map_err(|e| format!("{}: {}", dir.display(), e))?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter(|p| p.file_name().and_then(|n| n.to_str()).is_some_and(is_interval_file))
+        .collect();
+    if files.is_empty() {
+        return Err(format!("no interval files (YYYY-MM.data) in {}", dir.display()));
+    }
+    files.sort();
+
+    let mut batch = ImportBatch::default();
+    for file in files {
+        let name = file.file_name().and_then(|n| n.to_str()).unwrap_or_default().to_string();
+        let file_batch = read(&read_file(&file)?, Some(&name), options);
+        batch.sessions.extend(file_batch.sessions);
+        batch.errors.extend(file_batch.errors);
+    }
+    Ok(batch)
+}
+
+/// Monthly files like `2026-09.data`; `tags.data` and the undo log are not intervals
+fn is_interval_file(name: &str) -> bool {
+    name.strip_suffix(".data")
+        .and_then(|month| month.split_once('-'))
+        .is_some_and(|(year, month)| {
+            year.len() == 4 && month.len() == 2 && format!("{}{}", year, month).chars().all(|c| c.is_ascii_digit())
+        })
+}
+
+/// Reads the intervals of one file. Running intervals have no end yet and are reported as errors.
+pub fn read(contents: &str, file: Option<&str>, options: &TimewarriorOptions) -> ImportBatch {
+    let mut batch = ImportBatch::default();
+    for (index, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+        let mut position = Position::line(index + 1);
+        if let Some(file) = file {
+            position = position.in_file(file);
+        }
+        match read_interval(line, options) {
+            Ok(mut session) => {
+                session.position = position;
+                batch.sessions.push(session);
+            }
+            Err(message) => batch.errors.push(RowError { position, message }),
+        }
+    }
+    batch
+}
+
+fn read_interval(line: &str, options: &TimewarriorOptions) -> Result<ImportedSession, String> {
+    let rest = line.strip_prefix("inc ").ok_or_else(|| format!("not an interval: '{}'", line))?;
+    let (times, rest) = match rest.split_once(" # ") {
+        Some((times, rest)) => (times, rest),
+        None => (rest.strip_suffix(" #").unwrap_or(rest), ""),
+    };
+
+    let (start, end) = match times.split_once(" - ") {
+        Some((start, end)) => (parse_time(start.trim())?, parse_time(end.trim())?),
+        None => return Err("interval is still running".to_string()),
+    };
+    let seconds = (end - start).num_seconds();
+    if seconds < 0 {
+        return Err("ends before it starts".to_string());
+    }
+    let duration = u32::try_from((seconds + 30) / 60).map_err(|_| "too long for a session".to_string())?;
+    if duration == 0 {
+        return Err("shorter than a minute".to_string());
+    }
+
+    // Tags, then an optional annotation after a second '#'
+    let words = split_words(rest)?;
+    let (tags, annotation) = match words.iter().position(|w| w.as_str() == "#") {
+        Some(i) => (&words[..i], words[i + 1..].first()),
+        None => (&words[..], None),
+    };
+    let (category, tags) = split_category(tags, options)?;
+
+    Ok(ImportedSession {
+        position: Position::default(),
+        id: None,
+        import_key: Some(import_key("timew", &[start.to_rfc3339().as_str(), end.to_rfc3339().as_str()])),
+        title: annotation.cloned().unwrap_or_else(|| "Untitled".to_string()),
+        category,
+        tags,
+        start,
+        duration,
+    })
+}
+
+/// Picks the category among the tags and returns it with the remaining tags
+fn split_category(tags: &[String], options: &TimewarriorOptions) -> Result<(String, Vec<String>), String> {
+    let without = |index: usize| tags.iter().enumerate().filter(|(i, _)| *i != index).map(|(_, t)| t.clone()).collect();
+
+    for (index, tag) in tags.iter().enumerate() {
+        if let Some((_, category)) = options.tag_rules.iter().find(|(rule, _)| names_match(rule, tag)) {
+            return Ok((category.clone(), without(index)));
+        }
+    }
+    // Timewarrior writes tags sorted, so these came from metron's export
+    if tags.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Ok((tags[0].clone(), tags[1..].to_vec()));
+    }
+
+    let known: Vec<usize> = (0..tags.len()).filter(|&i| is_category(&tags[i], &options.categories)).collect();
+    match known.as_slice() {
+        [index] => return Ok((tags[*index].clone(), without(*index))),
+        [] => {}
+        _ => {
+            let names: Vec<&str> = known.iter().map(|&i| tags[i].as_str()).collect();
+            return Err(format!("tags {} are all categories, pick one with --map-tag", names.join(", ")));
+        }
+    }
+
+    match (tags, &options.default_category) {
+        ([category], _) => Ok((category.clone(), Vec::new())),
+        (_, Some(category)) => Ok((category.clone(), tags.to_vec())),
+        ([], None) => Err("no tags (set a category for these with --default-category)".to_string()),
+        (_, None) => Err(format!("no category among the tags {} (map one with --map-tag or set --default-category)",
+            tags.join(", "))),
+    }
+}
+
+/// Whether a tag is the path or the name of one of the categories
+fn is_category(tag: &str, categories: &[String]) -> bool {
+    categories.iter().any(|path| {
+        names_match(path, tag) || path.rsplit('/').next().is_some_and(|name| names_match(name, tag))
+    })
+}
+
+fn parse_time(s: &str) -> Result<DateTime<Utc>, String> {
+    NaiveDateTime::parse_from_str(s, TIME_FORMAT)
+        .map(|naive| naive.and_utc())
+        .map_err(|_| format!("invalid time '{}'", s))
+}
+
+/// Splits tags and annotations on spaces, keeping double-quoted words together.
+/// A bare `#` is kept as a word of its own.
+fn split_words(s: &str) -> Result<Vec<String>, String> {
+    let mut words = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c == ' ' {
+            chars.next();
+            continue;
+        }
+        let mut word = String::new();
+        if c == '"' {
+            chars.next();
+            loop {
+                match chars.next() {
+                    Some('\\') => word.extend(chars.next()),
+                    Some('"') => break,
+                    Some(c) => word.push(c),
+                    None => return Err(format!("unterminated quote in '{}'", s)),
+                }
+            }
+        } else {
+            while let Some(&c) = chars.peek().filter(|c| **c != ' ') {
+                word.push(c);
+                chars.next();
+            }
+        }
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Quotes a tag or annotation unless it is a plain word
+fn quote(word: &str) -> String {
+    let plain = !word.is_empty() && word.chars().all(|c| c.is_alphanumeric() || "-_.:/".contains(c));
+    if plain && word != "#" {
+        word.to_string()
+    } else {
+        format!("\"{}\"", word.replace('\\', "\\\\").replace('"', "\\\""))
+    }
+}
+
+/// Writes sessions as intervals: the category path as first tag, then the
+/// tags, and the title as annotation. Intervals last as long as the tracked
+/// duration so Timewarrior's totals match metron's.
+pub fn write(out: &mut dyn Write, data: &MetronData, sessions: &[&Session]) -> Result<(), String> {
+    for session in sessions {
+        let end = session.start + chrono::Duration::minutes(session.duration as i64);
+        let mut tags = vec![quote(&data.category_path(&session.category_id))];
+        tags.extend(session.tag_ids.iter().map(|id| quote(data.tag_name(id))));
+        writeln!(out, "inc {} - {} # {} # {}",
+            session.start.format(TIME_FORMAT), end.format(TIME_FORMAT), tags.join(" "), quote(&session.title))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
+
+/// Groups sessions into Timewarrior's monthly files, by the month they start in
+pub fn monthly_files<'s>(sessions: &[&'s Session]) -> BTreeMap<String, Vec<&'s Session>> {
+    let mut files: BTreeMap<String, Vec<&Session>> = BTreeMap::new();
+    for session in sessions {
+        files.entry(format!("{}.data", session.start.format("%Y-%m"))).or_default().push(session);
+    }
+    files
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> TimewarriorOptions {
+        TimewarriorOptions { categories: vec!["Acme".to_string(), "Acme/Website".to_string()], ..Default::default() }
+    }
+
+    #[test]
+    fn finds_the_category_among_sorted_tags() {
+        let batch = read("inc 20260901T090000Z - 20260901T103000Z # Acme design # mockups\n", None, &options());
+        let session = &batch.sessions[0];
+        assert_eq!((session.category.as_str(), session.tags.as_slice()), ("Acme", ["design".to_string()].as_slice()));
+        assert_eq!(session.duration, 90);
+    }
+
+    #[test]
+    fn keeps_the_order_of_metron_exports() {
+        let batch = read("inc 20260901T090000Z - 20260901T100000Z # \"Acme/Website\" zeta design # x\n", None, &options());
+        assert_eq!(batch.sessions[0].category, "Acme/Website");
+        assert_eq!(batch.sessions[0].tags, vec!["zeta", "design"]);
+    }
+
+    #[test]
+    fn rejects_intervals_too_long_for_a_session() {
+        let batch = read("inc 00010101T000000Z - 99991231T000000Z # Acme\n", None, &options());
+        assert!(batch.sessions.is_empty());
+        assert_eq!(batch.errors[0].message, "too long for a session");
+    }
+}
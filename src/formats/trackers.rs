@@ -0,0 +1,289 @@
+//! Readers for the detailed CSV exports of hosted trackers. Projects become
+//! categories (below their client), tags stay tags.
+
+use super::{import_key, parse_clock_duration, to_utc, ImportBatch, ImportedSession, Position, RowError};
+use crate::models::names_match;
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use clap::ValueEnum;
+use std::collections::HashMap;
+use std::io::Read;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Tracker {
+    Toggl,
+    Clockify,
+    Harvest,
+}
+
+/// Where a tracker keeps each field. Each entry lists the header names used by
+/// different versions of its export.
+struct Layout {
+    title: &'static [&'static str],
+    client: &'static [&'static str],
+    project: &'static [&'static str],
+    task: &'static [&'static str],
+    tags: &'static [&'static str],
+    date: &'static [&'static str],
+    time: &'static [&'static str],
+    duration: &'static [&'static str], // H:MM:SS
+    hours: &'static [&'static str],    // decimal hours
+    date_format: &'static str,
+}
+
+impl Tracker {
+    fn name(&self) -> &'static str {
+        match self {
+            Tracker::Toggl => "toggl",
+            Tracker::Clockify => "clockify",
+            Tracker::Harvest => "harvest",
+        }
+    }
+
+    fn layout(&self) -> Layout {
+        match self {
+            Tracker::Toggl => Layout {
+                title: &["Description"],
+                client: &["Client"],
+                project: &["Project"],
+                task: &["Task"],
+                tags: &["Tags"],
+                date: &["Start date"],
+                time: &["Start time"],
+                duration: &["Duration"],
+                hours: &[],
+                date_format: "%Y-%m-%d",
+            },
+            Tracker::Clockify => Layout {
+                title: &["Description"],
+                client: &["Client"],
+                project: &["Project"],
+                task: &["Task"],
+                tags: &["Tags"],
+                date: &["Start Date"],
+                time: &["Start Time"],
+                duration: &["Duration (h)"],
+                hours: &["Duration (decimal)"],
+                date_format: "%m/%d/%Y",
+            },
+            // Harvest only records hours per day, so sessions are laid out one after another
+            Tracker::Harvest => Layout {
+                title: &["Notes"],
+                client: &["Client"],
+                project: &["Project"],
+                task: &["Task"],
+                tags: &[],
+                date: &["Date", "Spent Date"],
+                time: &[],
+                duration: &[],
+                hours: &["Hours"],
+                date_format: "%Y-%m-%d",
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TrackerOptions {
+    pub timezone: Tz,
+    /// Overrides the tracker's usual date format, e.g. for Clockify set to DD/MM/YYYY
+    pub date_format: Option<String>,
+    /// Use the project alone as category instead of Client/Project
+    pub no_client: bool,
+    /// Category for entries without a project or client
+    pub default_category: Option<String>,
+    /// Add the task of an entry as a tag
+    pub task_as_tag: bool,
+    /// When the first session of a day starts, for exports without start times
+    pub day_start: NaiveTime,
+}
+
+/// Reads a tracker's detailed CSV export. Rows that can't be read are collected as errors.
+pub fn read(reader: impl Read, tracker: Tracker, options: &TrackerOptions) -> Result<ImportBatch, String> {
+    let layout = tracker.layout();
+    let date_format = options.date_format.as_deref().unwrap_or(layout.date_format);
+
+    let mut reader = ::csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(::csv::Trim::All)
+        .from_reader(reader);
+    let headers = reader.headers().map_err(|e| e.to_string())?.clone();
+    let index = |names: &[&str]| headers.iter().position(|h| names.iter().any(|n| names_match(h, n)));
+
+    let columns = [
+        ("title", index(layout.title)),
+        ("client", index(layout.client)),
+        ("project", index(layout.project)),
+        ("task", index(layout.task)),
+        ("tags", index(layout.tags)),
+        ("date", index(layout.date)),
+        ("time", index(layout.time)),
+        ("duration", index(layout.duration)),
+        ("hours", index(layout.hours)),
+    ];
+    let columns: HashMap<&str, usize> = columns.into_iter()
+        .filter_map(|(field, i)| i.map(|i| (field, i)))
+        .collect();
+
+    let missing = |fields: &[&[&str]]| fields.iter().all(|names| index(names).is_none());
+    if missing(&[layout.project, layout.client]) || missing(&[layout.date]) || missing(&[layout.duration, layout.hours]) {
+        return Err(format!("this doesn't look like a detailed {} export (expected columns {}, {}, {})",
+            tracker.name(), layout.project[0], layout.date[0],
+            layout.duration.first().or(layout.hours.first()).unwrap_or(&"Duration")));
+    }
+
+    let mut batch = ImportBatch::default();
+    let mut day_offsets: HashMap<NaiveDate, u32> = HashMap::new();
+    let mut occurrences: HashMap<String, usize> = HashMap::new();
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let position = Position::line(e.position().map(|p| p.line() as usize).unwrap_or(0));
+                batch.errors.push(RowError { position, message: e.to_string() });
+                continue;
+            }
+        };
+        let position = Position::line(record.position().map(|p| p.line() as usize).unwrap_or(0));
+        let field = |name: &str| {
+            columns.get(name)
+                .and_then(|i| record.get(*i))
+                .filter(|v| !v.is_empty())
+        };
+
+        match read_row(&field, date_format, &mut day_offsets, options) {
+            Ok(mut session) => {
+                // Identical entries in one export are told apart by their position among each other
+                let fields: Vec<&str> = ["date", "time", "duration", "hours", "client", "project", "task", "title"]
+                    .iter()
+                    .map(|f| field(f).unwrap_or(""))
+                    .collect();
+                let key = import_key(tracker.name(), &fields);
+                let occurrence = occurrences.entry(key.clone()).or_default();
+                *occurrence += 1;
+                session.import_key = Some(match *occurrence {
+                    1 => key,
+                    n => format!("{}-{}", key, n),
+                });
+                session.position = position;
+                batch.sessions.push(session);
+            }
+            Err(message) => batch.errors.push(RowError { position, message }),
+        }
+    }
+
+    Ok(batch)
+}
+
+fn read_row<'r>(
+    field: &dyn Fn(&str) -> Option<&'r str>,
+    date_format: &str,
+    day_offsets: &mut HashMap<NaiveDate, u32>,
+    options: &TrackerOptions,
+) -> Result<ImportedSession, String> {
+    let category = match (field("client").filter(|_| !options.no_client), field("project")) {
+        (Some(client), Some(project)) => format!("{}/{}", client, project),
+        (None, Some(name)) | (Some(name), None) => name.to_string(),
+        (None, None) => options.default_category.clone()
+            .ok_or("no project (set a category for these with --default-category)")?,
+    };
+
+    let duration = match (field("duration"), field("hours")) {
+        (Some(duration), _) => parse_clock_duration(duration)?,
+        (None, Some(hours)) => parse_hours(hours)?,
+        (None, None) => return Err("no duration".to_string()),
+    };
+    if duration == 0 {
+        return Err("duration is zero".to_string());
+    }
+
+    let date_text = field("date").ok_or("no date")?;
+    let date = NaiveDate::parse_from_str(date_text, date_format)
+        .map_err(|_| format!("invalid date '{}', expected format {} (see --date-format)", date_text, date_format))?;
+    let start = match field("time") {
+        Some(time) => at(date, parse_clock_time(time)?, options.timezone)?,
+        None => {
+            let offset = day_offsets.entry(date).or_default();
+            let start = at(date, options.day_start, options.timezone)? + chrono::Duration::minutes(*offset as i64);
+            *offset = offset.checked_add(duration).ok_or_else(|| format!("the entries of {} are too long", date))?;
+            start
+        }
+    };
+
+    let mut tags: Vec<String> = field("tags")
+        .map(|tags| tags.split(',').map(str::trim).filter(|t| !t.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default();
+    if let Some(task) = field("task").filter(|_| options.task_as_tag) {
+        tags.push(task.to_string());
+    }
+
+    Ok(ImportedSession {
+        position: Position::default(),
+        id: None,
+        import_key: None,
+        title: field("title").unwrap_or("Untitled").to_string(),
+        category,
+        tags,
+        start,
+        duration,
+    })
+}
+
+fn at(date: NaiveDate, time: NaiveTime, tz: Tz) -> Result<DateTime<Utc>, String> {
+    to_utc(date.and_time(time), tz)
+}
+
+/// Times of day in 24-hour or 12-hour notation, with or without seconds
+fn parse_clock_time(s: &str) -> Result<NaiveTime, String> {
+    ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"].iter()
+        .find_map(|format| NaiveTime::parse_from_str(s, format).ok())
+        .ok_or_else(|| format!("invalid time '{}'", s))
+}
+
+/// Decimal hours like `1.25`, rounded to the minute
+fn parse_hours(s: &str) -> Result<u32, String> {
+    let hours: f64 = s.replace(',', ".").parse()
+        .map_err(|_| format!("invalid hours '{}'", s))?;
+    if !(0.0..=24.0 * 366.0).contains(&hours) {
+        return Err(format!("invalid hours '{}'", s));
+    }
+    Ok((hours * 60.0).round() as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options() -> TrackerOptions {
+        TrackerOptions {
+            timezone: Tz::UTC,
+            date_format: None,
+            no_client: false,
+            default_category: None,
+            task_as_tag: false,
+            day_start: NaiveTime::from_hms_opt(9, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn reads_toggl_entries() {
+        let export = "Description,Client,Project,Tags,Start date,Start time,Duration\n\
+            Mockups,Acme,Website,\"design, review\",2026-09-01,09:00:00,01:30:00\n";
+        let batch = read(export.as_bytes(), Tracker::Toggl, &options()).unwrap();
+        assert!(batch.errors.is_empty());
+        let session = &batch.sessions[0];
+        assert_eq!(session.category, "Acme/Website");
+        assert_eq!(session.tags, vec!["design", "review"]);
+        assert_eq!(session.duration, 90);
+    }
+
+    #[test]
+    fn rejects_oversized_durations() {
+        let export = "Description,Client,Project,Tags,Start date,Start time,Duration\n\
+            Mockups,Acme,Website,,2026-09-01,09:00:00,99999999:00:00\n";
+        let batch = read(export.as_bytes(), Tracker::Toggl, &options()).unwrap();
+        assert!(batch.sessions.is_empty());
+        assert_eq!(batch.errors.len(), 1);
+        assert!(batch.errors[0].message.contains("invalid duration"));
+    }
+}
@@ -0,0 +1,83 @@
+//! Watson's `frames` file: a JSON array of
+//! `[start, stop, project, id, tags, updated_at]` with Unix timestamps.
+//! Projects become categories; Watson frames have no title.
+
+use super::{ImportBatch, ImportedSession, Position, RowError};
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+
+pub fn read(contents: &str) -> Result<ImportBatch, String> {
+    let frames: Vec<Value> = serde_json::from_str(contents)
+        .map_err(|e| format!("not a Watson frames file: {}", e))?;
+
+    let mut batch = ImportBatch::default();
+    for (index, frame) in frames.iter().enumerate() {
+        let position = Position::entry(index + 1);
+        match read_frame(frame) {
+            Ok(mut session) => {
+                session.position = position;
+                batch.sessions.push(session);
+            }
+            Err(message) => batch.errors.push(RowError { position, message }),
+        }
+    }
+    Ok(batch)
+}
+
+fn read_frame(frame: &Value) -> Result<ImportedSession, String> {
+    let invalid = || format!("not a frame: {}", frame);
+    let fields = frame.as_array().filter(|f| f.len() >= 4).ok_or_else(invalid)?;
+
+    let timestamp = |value: &Value| {
+        value.as_i64()
+            .and_then(|secs| DateTime::<Utc>::from_timestamp(secs, 0))
+            .ok_or_else(invalid)
+    };
+    let start = timestamp(&fields[0])?;
+    let stop = timestamp(&fields[1])?;
+    let project = fields[2].as_str().filter(|p| !p.is_empty()).ok_or_else(invalid)?;
+    let id = fields[3].as_str().ok_or_else(invalid)?;
+    let tags = fields.get(4)
+        .and_then(Value::as_array)
+        .map(|tags| tags.iter().filter_map(Value::as_str).map(str::to_string).collect())
+        .unwrap_or_default();
+
+    let seconds = (stop - start).num_seconds();
+    if seconds < 0 {
+        return Err("ends before it starts".to_string());
+    }
+    let duration = u32::try_from((seconds + 30) / 60).map_err(|_| "too long for a session".to_string())?;
+    if duration == 0 {
+        return Err("shorter than a minute".to_string());
+    }
+
+    Ok(ImportedSession {
+        position: Position::default(),
+        id: None,
+        import_key: Some(format!("watson:{}", id)),
+        title: "Untitled".to_string(),
+        category: project.to_string(),
+        tags,
+        start,
+        duration,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_frames() {
+        let batch = read(r#"[[1788253200, 1788258600, "Acme", "a1", ["design"], 1788258600]]"#).unwrap();
+        assert_eq!(batch.sessions[0].category, "Acme");
+        assert_eq!(batch.sessions[0].duration, 90);
+    }
+
+    #[test]
+    fn rejects_frames_too_long_for_a_session() {
+        let batch = read(r#"[[0, 1000000000000, "Acme", "a1", [], 0]]"#).unwrap();
+        assert!(batch.sessions.is_empty());
+        assert_eq!(batch.errors[0].message, "too long for a session");
+    }
+}
@@ -1,9 +1,18 @@
-use clap::{Parser, Subcommand, Args};
+use clap::{Parser, Subcommand, Args, ArgAction, ArgGroup};
 
 mod models;
 mod storage;
 mod managers;
+mod formats;
+mod report;
+mod utils;
 
+use std::path::PathBuf;
+use chrono::{DateTime, Datelike, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Tz;
+use formats::csv::{ColumnMap, CsvOptions};
+use formats::trackers::{Tracker, TrackerOptions};
+use models::{BreakRule, MergeRule, QuotaPolicy, Rate, Rounding};
 use storage::Storage;
 use managers::*;
 
@@ -32,10 +41,363 @@ pub enum Commands {
     Session(SessionCommands),
     /// Show analytics
     Analysis(AnalysisArgs),
-    /// Set total weekly quota
+    /// Write a timesheet for a period as Markdown or HTML
+    Report(ReportArgs),
+    /// Summarise a client's billable time with rates and amounts
+    Invoice(InvoiceArgs),
+    /// Show tracked time per day as a calendar heatmap (default: this year)
+    Heatmap {
+        /// Show a month (YYYY-MM)
+        #[arg(long, value_parser = utils::parse_month, conflicts_with = "year")]
+        month: Option<NaiveDate>,
+        /// Show a year
+        #[arg(long)]
+        year: Option<i32>,
+        /// Only count this category and its subcategories
+        #[arg(short, long)]
+        category: Option<String>,
+    },
+    /// Show this week's progress toward category quotas and targets
+    Status,
+    /// Check tracked days against working-time rules
+    Compliance {
+        /// Time period to check (day, week, month, year)
+        #[arg(short, long, default_value = "month")]
+        period: String,
+        /// Time zone the working days are in
+        #[arg(long, default_value = "UTC", value_parser = utils::parse_timezone)]
+        timezone: Tz,
+    },
+    /// Check the data file for inconsistencies and sessions breaking the current rules
+    Doctor {
+        /// Repair what can be repaired without changing tracked time
+        #[arg(long)]
+        fix: bool,
+    },
+    /// Set or clear the total weekly quota
+    #[command(group(ArgGroup::new("quota_or_clear").required(true).args(["quota", "clear"])))]
     SetQuota {
-        /// Total weekly quota in hours
-        hours: u32,
+        /// Total weekly quota, e.g. 38h30m or 38.5h (bare numbers are hours)
+        #[arg(value_parser = utils::parse_quota)]
+        quota: Option<u32>,
+        /// Scale category quotas down proportionally if they don't fit
+        #[arg(long, requires = "quota")]
+        scale: bool,
+        /// Remove the total weekly quota
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Show or change settings
+    #[command(subcommand)]
+    Config(ConfigCommands),
+    /// Write sessions to other formats
+    #[command(subcommand)]
+    Export(ExportCommands),
+    /// Read sessions from other formats
+    #[command(subcommand)]
+    Import(ImportCommands),
+}
+
+#[derive(Subcommand)]
+pub enum ExportCommands {
+    /// Export sessions as CSV
+    Csv {
+        #[command(flatten)]
+        range: RangeArgs,
+        #[command(flatten)]
+        csv: CsvArgs,
+        /// File to write (default: standard output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Export sessions as iCalendar events
+    Ics {
+        #[command(flatten)]
+        range: RangeArgs,
+        /// File to write (default: standard output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Export sessions in ledger/hledger timeclock format
+    Timeclock {
+        #[command(flatten)]
+        range: RangeArgs,
+        /// Time zone to write times in
+        #[arg(long, default_value = "UTC", value_parser = utils::parse_timezone)]
+        timezone: Tz,
+        /// File to write (default: standard output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Export sessions as org-mode CLOCK entries grouped by category
+    Org {
+        #[command(flatten)]
+        range: RangeArgs,
+        /// Time zone to write times in
+        #[arg(long, default_value = "UTC", value_parser = utils::parse_timezone)]
+        timezone: Tz,
+        /// File to write (default: standard output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Export sessions as Timewarrior intervals
+    Timewarrior {
+        #[command(flatten)]
+        range: RangeArgs,
+        /// Timewarrior data directory to write monthly files to (default: standard output)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ImportCommands {
+    /// Import sessions from a CSV file with a header row
+    Csv {
+        file: PathBuf,
+        #[command(flatten)]
+        csv: CsvArgs,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+    /// Import events from an iCalendar (.ics) file; recurring events are expanded up to now
+    Ics {
+        file: PathBuf,
+        #[command(flatten)]
+        range: RangeArgs,
+        /// Category for events whose title contains TEXT, as TEXT=CATEGORY; repeat for several
+        #[arg(long, value_parser = formats::ics::parse_rule)]
+        map_title: Vec<(String, String)>,
+        /// Category for all events of a calendar, as CALENDAR=CATEGORY
+        #[arg(long, value_parser = formats::ics::parse_rule)]
+        map_calendar: Vec<(String, String)>,
+        /// Category for events no rule matches
+        #[arg(long)]
+        default_category: Option<String>,
+        /// Time zone of times given without one
+        #[arg(long, default_value = "UTC", value_parser = utils::parse_timezone)]
+        timezone: Tz,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+    /// Import intervals from a Timewarrior data directory or .data file
+    Timewarrior {
+        /// Data directory (e.g. ~/.timewarrior/data) or a single YYYY-MM.data file
+        path: PathBuf,
+        /// Category for intervals with TAG, as TAG=CATEGORY; repeat for several
+        #[arg(long, value_parser = formats::ics::parse_rule)]
+        map_tag: Vec<(String, String)>,
+        /// Category for intervals without tags, or whose tags name no category
+        #[arg(long)]
+        default_category: Option<String>,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+    /// Import frames from Watson's frames file
+    Watson {
+        /// Usually ~/.config/watson/frames
+        file: PathBuf,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+    /// Import a Toggl Track detailed report (CSV)
+    Toggl {
+        file: PathBuf,
+        #[command(flatten)]
+        tracker: TrackerArgs,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+    /// Import a Clockify detailed report (CSV)
+    Clockify {
+        file: PathBuf,
+        #[command(flatten)]
+        tracker: TrackerArgs,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+    /// Import a Harvest detailed time report (CSV)
+    Harvest {
+        file: PathBuf,
+        #[command(flatten)]
+        tracker: TrackerArgs,
+        /// Start of the first session of each day, as Harvest has no start times (HH:MM)
+        #[arg(long, default_value = "09:00", value_parser = utils::parse_time)]
+        day_start: NaiveTime,
+        #[command(flatten)]
+        import: ImportArgs,
+    },
+}
+
+/// Which sessions to include
+#[derive(Args)]
+pub struct RangeArgs {
+    /// Time period around today (day, week, month, year)
+    #[arg(short, long)]
+    period: Option<String>,
+    /// First day to include (YYYY-MM-DD)
+    #[arg(long, value_parser = utils::parse_date)]
+    from: Option<NaiveDate>,
+    /// Last day to include (YYYY-MM-DD)
+    #[arg(long, value_parser = utils::parse_date)]
+    to: Option<NaiveDate>,
+}
+
+impl From<RangeArgs> for utils::DateRange {
+    fn from(args: RangeArgs) -> Self {
+        Self { period: args.period, from: args.from, to: args.to }
+    }
+}
+
+#[derive(Args)]
+pub struct CsvArgs {
+    /// Column for a field as FIELD=COLUMN (fields: id, title, category, tags,
+    /// start, end, duration, date, time); repeat for several
+    #[arg(short, long = "map", value_parser = formats::csv::parse_mapping)]
+    mappings: Vec<(String, String)>,
+    /// Time zone of the times in the file
+    #[arg(long, default_value = "UTC", value_parser = utils::parse_timezone)]
+    timezone: Tz,
+    /// chrono format of the times in the file
+    #[arg(long, default_value = "%Y-%m-%d %H:%M", value_parser = utils::parse_time_format)]
+    date_format: String,
+    /// Character separating tags within the tags column
+    #[arg(long, default_value = ",")]
+    tag_separator: char,
+}
+
+impl CsvArgs {
+    fn into_options(self) -> std::result::Result<CsvOptions, String> {
+        let mut columns = ColumnMap::default();
+        for (field, column) in &self.mappings {
+            columns.set(field, column)?;
+        }
+        Ok(CsvOptions {
+            columns,
+            timezone: self.timezone,
+            date_format: self.date_format,
+            tag_separator: self.tag_separator,
+        })
+    }
+}
+
+/// How to turn another tracker's entries into sessions
+#[derive(Args)]
+pub struct TrackerArgs {
+    /// Time zone of the times in the file
+    #[arg(long, default_value = "UTC", value_parser = utils::parse_timezone)]
+    timezone: Tz,
+    /// chrono format of the dates in the file, if not the tracker's default
+    #[arg(long, value_parser = utils::parse_time_format)]
+    date_format: Option<String>,
+    /// Use projects as categories without their client as parent
+    #[arg(long)]
+    no_client: bool,
+    /// Category for entries without a project
+    #[arg(long)]
+    default_category: Option<String>,
+    /// Add the task of each entry as a tag
+    #[arg(long)]
+    task_as_tag: bool,
+}
+
+impl TrackerArgs {
+    fn into_options(self, day_start: NaiveTime) -> TrackerOptions {
+        TrackerOptions {
+            timezone: self.timezone,
+            date_format: self.date_format,
+            no_client: self.no_client,
+            default_category: self.default_category,
+            task_as_tag: self.task_as_tag,
+            day_start,
+        }
+    }
+}
+
+/// How to handle unknown names and invalid rows
+#[derive(Args)]
+pub struct ImportArgs {
+    /// Create categories and tags that don't exist yet
+    #[arg(long)]
+    create_missing: bool,
+    /// Import the valid rows even if others are invalid
+    #[arg(long)]
+    skip_invalid: bool,
+    /// Show what would be imported without saving
+    #[arg(long)]
+    dry_run: bool,
+    /// Round durations that don't fit a category's block size (down, nearest, up)
+    #[arg(long)]
+    rounding: Option<Rounding>,
+}
+
+impl From<ImportArgs> for ImportOptions {
+    fn from(args: ImportArgs) -> Self {
+        Self {
+            create_missing: args.create_missing,
+            skip_invalid: args.skip_invalid,
+            dry_run: args.dry_run,
+            rounding: args.rounding,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommands {
+    /// Show current settings
+    Show,
+    /// Set what happens when a session exceeds its category's weekly quota
+    QuotaPolicy {
+        /// ignore, warn or block
+        policy: QuotaPolicy,
+    },
+    /// Set the maximum number of tags
+    #[command(group(ArgGroup::new("limit_or_off").required(true).args(["limit", "off"])))]
+    TagLimit {
+        limit: Option<u32>,
+        /// Allow any number of tags
+        #[arg(long)]
+        off: bool,
+    },
+    /// Require tags on every session (any tag unless a group is given)
+    RequireTag {
+        /// Require a tag of this group
+        #[arg(short, long, conflicts_with = "off")]
+        group: Option<String>,
+        /// Don't require tags
+        #[arg(long)]
+        off: bool,
+    },
+    /// Set the default timeblock size and rounding
+    #[command(group(ArgGroup::new("changes").required(true).multiple(true).args(["block", "rounding"])))]
+    Timeblock {
+        /// Block size in minutes (5, 6, 10, 15 or 30)
+        #[arg(short, long, value_parser = utils::parse_block_size)]
+        block: Option<u32>,
+        /// Rounding for sessions ended early (down, nearest, up, none)
+        #[arg(short, long)]
+        rounding: Option<Rounding>,
+    },
+    /// Configure working-time rules (durations like 10h or 30m; bare numbers are minutes)
+    Compliance {
+        /// Restore the default (German) rules before applying other changes
+        #[arg(long)]
+        reset: bool,
+        /// Maximum working time per day
+        #[arg(long, value_parser = utils::parse_minutes)]
+        max_daily: Option<u32>,
+        /// Required break as AFTER:BREAK, e.g. 6h:30m (repeat to replace all break rules)
+        #[arg(long = "break", value_parser = utils::parse_break_rule)]
+        breaks: Vec<BreakRule>,
+        /// Shortest gap between sessions that counts as a break
+        #[arg(long, value_parser = utils::parse_minutes)]
+        break_segment: Option<u32>,
+        /// Minimum rest between the end of one day and the start of the next
+        #[arg(long, value_parser = utils::parse_minutes)]
+        rest: Option<u32>,
+        /// Warn on session start/end when a rule is broken (true/false)
+        #[arg(long)]
+        warn: Option<bool>,
     },
 }
 
@@ -43,21 +405,103 @@ pub enum Commands {
 pub enum CategoryCommands {
     /// Create a new category
     Create {
-        /// Name of the category
+        /// Name of the category, or a path like Acme/Website for a subcategory
         name: String,
-        /// Weekly quota in hours
-        #[arg(short, long)]
+        /// Weekly quota, e.g. 7h30m or 7.5h (bare numbers are hours)
+        #[arg(short, long, value_parser = utils::parse_quota)]
         quota: u32,
+        /// Minimum time per week to aim for, e.g. 5h
+        #[arg(long, value_parser = utils::parse_quota)]
+        target: Option<u32>,
+        #[arg(long)]
+        description: Option<String>,
+        /// Color name (red, green, blue, ...) or #rrggbb
+        #[arg(long, value_parser = utils::parse_color)]
+        color: Option<String>,
     },
     /// List all categories
-    List,
-    /// Update an existing category's quota
+    List {
+        /// Include archived categories
+        #[arg(short, long)]
+        all: bool,
+    },
+    /// Update an existing category's quota, target, description or color
+    #[command(group(ArgGroup::new("changes").required(true).multiple(true).args(["quota", "target", "description", "color"])))]
     Update {
         name: String,
+        /// Weekly quota, e.g. 7h30m or 7.5h (bare numbers are hours)
+        #[arg(short, long, value_parser = utils::parse_quota)]
+        quota: Option<u32>,
+        /// Minimum time per week to aim for, e.g. 5h (0 removes the target)
+        #[arg(long, value_parser = utils::parse_quota)]
+        target: Option<u32>,
+        /// Description ("" removes it)
+        #[arg(long)]
+        description: Option<String>,
+        /// Color name (red, green, blue, ...) or #rrggbb ("none" removes it)
+        #[arg(long, value_parser = utils::parse_color)]
+        color: Option<String>,
+    },
+    /// Rename a category, updating all sessions that use it
+    Rename {
+        name: String,
+        new_name: String,
+    },
+    /// Move all sessions of one category into another and remove the first
+    Merge {
+        from: String,
+        into: String,
+        /// How to combine the quotas and targets (sum, keep, max)
+        #[arg(short, long, default_value = "sum")]
+        quota: MergeRule,
+    },
+    /// Retire a category while keeping its history
+    Archive {
+        name: String,
+    },
+    /// Reactivate an archived category
+    Unarchive {
+        name: String,
+    },
+    /// Override the timeblock size or rounding for a category
+    #[command(group(ArgGroup::new("changes").required(true).multiple(true).args(["block", "rounding", "clear"])))]
+    Timeblock {
+        name: String,
+        /// Block size in minutes (5, 6, 10, 15 or 30)
+        #[arg(short, long, value_parser = utils::parse_block_size)]
+        block: Option<u32>,
+        /// Rounding for sessions ended early (down, nearest, up, none)
         #[arg(short, long)]
-        quota: u32,
+        rounding: Option<Rounding>,
+        /// Go back to the global timeblock settings
+        #[arg(long)]
+        clear: bool,
     },
-    /// Delete a category
+    /// Set the hourly rate for a category and its subcategories
+    #[command(group(ArgGroup::new("rate_or_clear").required(true).args(["rate", "clear"])))]
+    Rate {
+        name: String,
+        /// Amount per hour with an optional currency, e.g. 85 or "85.50 EUR"
+        #[arg(value_parser = utils::parse_rate)]
+        rate: Option<Rate>,
+        /// Go back to the parent's rate
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Override the tag rule for a category and its subcategories
+    RequireTag {
+        name: String,
+        /// Require a tag of this group
+        #[arg(short, long, conflicts_with_all = ["off", "clear"])]
+        group: Option<String>,
+        /// Don't require tags in this category
+        #[arg(long, conflicts_with = "clear")]
+        off: bool,
+        /// Go back to the parent's or global rule
+        #[arg(long)]
+        clear: bool,
+    },
+    /// Delete a category; refused if it has subcategories (use category archive to hide those)
     Delete {
         name: String,
     },
@@ -68,13 +512,87 @@ pub enum TagCommands {
     /// Create a new tag
     Create {
         name: String,
+        /// Tag group to put the tag in
+        #[arg(short, long)]
+        group: Option<String>,
+        #[arg(long)]
+        description: Option<String>,
+        /// Color name (red, green, blue, ...) or #rrggbb
+        #[arg(long, value_parser = utils::parse_color)]
+        color: Option<String>,
+    },
+    /// Change a tag's description or color
+    #[command(group(ArgGroup::new("changes").required(true).multiple(true).args(["description", "color"])))]
+    Update {
+        name: String,
+        /// Description ("" removes it)
+        #[arg(long)]
+        description: Option<String>,
+        /// Color name (red, green, blue, ...) or #rrggbb ("none" removes it)
+        #[arg(long, value_parser = utils::parse_color)]
+        color: Option<String>,
+    },
+    /// Set the hourly rate for sessions with a tag, overriding their category's
+    #[command(group(ArgGroup::new("rate_or_clear").required(true).args(["rate", "clear"])))]
+    Rate {
+        name: String,
+        /// Amount per hour with an optional currency, e.g. 120 or "120 EUR"
+        #[arg(value_parser = utils::parse_rate)]
+        rate: Option<Rate>,
+        /// Use the category's rate again
+        #[arg(long)]
+        clear: bool,
     },
     /// List all tags
     List,
+    /// Rename a tag, updating all sessions that use it
+    Rename {
+        name: String,
+        new_name: String,
+    },
+    /// Replace one tag with another on all sessions and remove the first
+    Merge {
+        from: String,
+        into: String,
+    },
     /// Delete a tag
     Delete {
         name: String,
     },
+    /// Organise tags into groups
+    #[command(subcommand)]
+    Group(TagGroupCommands),
+}
+
+#[derive(Subcommand)]
+pub enum TagGroupCommands {
+    /// Create a new tag group
+    Create {
+        name: String,
+        /// Allow only one tag of this group per session
+        #[arg(short, long)]
+        single_choice: bool,
+    },
+    /// Change whether only one tag of the group may be used per session
+    Update {
+        name: String,
+        /// true or false
+        #[arg(short, long, action = ArgAction::Set)]
+        single_choice: bool,
+    },
+    /// Move a tag into a group
+    Assign {
+        tag: String,
+        group: String,
+    },
+    /// Remove a tag from its group
+    Unassign {
+        tag: String,
+    },
+    /// Delete a tag group (its tags are kept)
+    Delete {
+        name: String,
+    },
 }
 
 #[derive(Subcommand)]
@@ -83,20 +601,75 @@ pub enum SessionCommands {
     Start {
         /// Title of the session
         title: String,
-        /// Category name
+        /// Category name or path, e.g. Acme/Website
         category: String,
         /// Tag names
         #[arg(short, long)]
         tags: Vec<String>,
-        /// Duration in minutes (must be multiple of 15)
+        /// Duration, e.g. 1h30m, 90m or 1.5h (must fit the timeblock)
+        #[arg(short, long, value_parser = utils::parse_minutes)]
+        duration: u32,
+        /// Leave the session off invoices
+        #[arg(long)]
+        non_billable: bool,
+    },
+    /// Log a session that already happened
+    Add {
+        /// Title of the session
+        title: String,
+        /// Category name or path, e.g. Acme/Website
+        category: String,
+        /// Tag names
         #[arg(short, long)]
+        tags: Vec<String>,
+        /// Duration, e.g. 1h30m, 90m or 1.5h (must fit the timeblock)
+        #[arg(short, long, value_parser = utils::parse_minutes)]
         duration: u32,
+        /// Start time (YYYY-MM-DD HH:MM, UTC)
+        #[arg(short, long, value_parser = utils::parse_datetime)]
+        start: DateTime<Utc>,
+        /// Leave the session off invoices
+        #[arg(long)]
+        non_billable: bool,
     },
-    /// End a session early (will round down to nearest 15 min)
+    /// End a session early (duration is rounded to the timeblock)
     End {
         /// Session ID
         id: String,
     },
+    /// Change a logged session
+    #[command(group(ArgGroup::new("changes").required(true).multiple(true)
+        .args(["title", "category", "tags", "clear_tags", "start", "duration", "rate", "clear_rate", "billable"])))]
+    Edit {
+        /// Session ID (or the start of it)
+        id: String,
+        #[arg(long)]
+        title: Option<String>,
+        /// Category name or path
+        #[arg(short, long)]
+        category: Option<String>,
+        /// Replace the tags
+        #[arg(short, long)]
+        tags: Vec<String>,
+        /// Remove all tags
+        #[arg(long, conflicts_with = "tags")]
+        clear_tags: bool,
+        /// Start time (YYYY-MM-DD HH:MM, UTC)
+        #[arg(short, long, value_parser = utils::parse_datetime)]
+        start: Option<DateTime<Utc>>,
+        /// Duration, e.g. 1h30m, 90m or 1.5h
+        #[arg(short, long, value_parser = utils::parse_minutes)]
+        duration: Option<u32>,
+        /// Hourly rate for this session only, e.g. 95 or "95 EUR"
+        #[arg(long, value_parser = utils::parse_rate)]
+        rate: Option<Rate>,
+        /// Use the rate of the session's tags or category again
+        #[arg(long, conflicts_with = "rate")]
+        clear_rate: bool,
+        /// Whether the session goes on invoices (true/false)
+        #[arg(long)]
+        billable: Option<bool>,
+    },
     /// List all sessions
     List,
     /// Delete a session
@@ -107,12 +680,61 @@ pub enum SessionCommands {
 
 #[derive(Args)]
 pub struct AnalysisArgs {
-    /// Time period to analyze (day, week, month, year)
-    #[arg(short, long, default_value = "week")]
-    period: String,
-    /// Filter by category
+    /// Sessions to analyze (default: this week)
+    #[command(flatten)]
+    range: RangeArgs,
+    /// Filter by category (includes its subcategories)
+    #[arg(short, long)]
+    category: Option<String>,
+}
+
+#[derive(Args)]
+pub struct ReportArgs {
+    /// Sessions to include (default: this week)
+    #[command(flatten)]
+    range: RangeArgs,
+    /// Only include this category and its subcategories
     #[arg(short, long)]
     category: Option<String>,
+    /// markdown, html, or json (the data templates get)
+    #[arg(short, long, default_value = "markdown")]
+    format: report::ReportFormat,
+    /// Render with a template from the templates folder of the config directory
+    #[arg(short, long, conflicts_with = "format")]
+    template: Option<String>,
+    /// Time zone for days and times in the report
+    #[arg(long, default_value = "UTC", value_parser = utils::parse_timezone)]
+    timezone: Tz,
+    /// File to write (default: standard output)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
+}
+
+#[derive(Args)]
+pub struct InvoiceArgs {
+    /// Category to bill, with its subcategories
+    #[arg(long)]
+    client: String,
+    /// Month to bill (YYYY-MM); default: this month
+    #[arg(long, value_parser = utils::parse_month, conflicts_with_all = ["period", "from", "to"])]
+    month: Option<NaiveDate>,
+    #[command(flatten)]
+    range: RangeArgs,
+    /// One line per session instead of per category and rate
+    #[arg(long)]
+    per_session: bool,
+    /// Tax to add to the subtotal in percent, e.g. 19
+    #[arg(long, value_parser = utils::parse_tax)]
+    tax: Option<f64>,
+    /// markdown or csv
+    #[arg(short, long, default_value = "markdown")]
+    format: report::invoice::InvoiceFormat,
+    /// Time zone for the dates of sessions
+    #[arg(long, default_value = "UTC", value_parser = utils::parse_timezone)]
+    timezone: Tz,
+    /// File to write (default: standard output)
+    #[arg(short, long)]
+    output: Option<PathBuf>,
 }
 
 fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
@@ -123,14 +745,35 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         Commands::Category(cmd) => {
             let mut manager = CategoryManager::new(&mut storage);
             match cmd {
-                CategoryCommands::Create { name, quota } => {
-                    manager.create_category(name, quota)?;
+                CategoryCommands::Create { name, quota, target, description, color } => {
+                    manager.create_category(name, quota, target, description, color)?;
+                }
+                CategoryCommands::List { all } => {
+                    manager.list_categories(all)?;
+                }
+                CategoryCommands::Update { name, quota, target, description, color } => {
+                    manager.update_category(name, quota, target, description, color)?;
+                }
+                CategoryCommands::Rename { name, new_name } => {
+                    manager.rename_category(name, new_name)?;
+                }
+                CategoryCommands::Merge { from, into, quota } => {
+                    manager.merge_categories(from, into, quota)?;
+                }
+                CategoryCommands::Archive { name } => {
+                    manager.archive_category(name)?;
                 }
-                CategoryCommands::List => {
-                    manager.list_categories()?;
+                CategoryCommands::Unarchive { name } => {
+                    manager.unarchive_category(name)?;
                 }
-                CategoryCommands::Update { name, quota } => {
-                    manager.update_category(name, quota)?;
+                CategoryCommands::Timeblock { name, block, rounding, clear } => {
+                    manager.set_timeblock(name, block, rounding, clear)?;
+                }
+                CategoryCommands::Rate { name, rate, clear: _ } => {
+                    manager.set_rate(name, rate)?;
+                }
+                CategoryCommands::RequireTag { name, group, off, clear } => {
+                    manager.set_tag_requirement(name, group, off, clear)?;
                 }
                 CategoryCommands::Delete { name } => {
                     manager.delete_category(name)?;
@@ -140,26 +783,62 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         Commands::Tag(cmd) => {
             let mut manager = TagManager::new(&mut storage);
             match cmd {
-                TagCommands::Create { name } => {
-                    manager.create_tag(name)?;
+                TagCommands::Create { name, group, description, color } => {
+                    manager.create_tag(name, group, description, color)?;
+                }
+                TagCommands::Update { name, description, color } => {
+                    manager.update_tag(name, description, color)?;
+                }
+                TagCommands::Rate { name, rate, clear: _ } => {
+                    manager.set_rate(name, rate)?;
                 }
                 TagCommands::List => {
                     manager.list_tags()?;
                 }
+                TagCommands::Rename { name, new_name } => {
+                    manager.rename_tag(name, new_name)?;
+                }
+                TagCommands::Merge { from, into } => {
+                    manager.merge_tags(from, into)?;
+                }
                 TagCommands::Delete { name } => {
                     manager.delete_tag(name)?;
                 }
+                TagCommands::Group(cmd) => match cmd {
+                    TagGroupCommands::Create { name, single_choice } => {
+                        manager.create_tag_group(name, single_choice)?;
+                    }
+                    TagGroupCommands::Update { name, single_choice } => {
+                        manager.update_tag_group(name, single_choice)?;
+                    }
+                    TagGroupCommands::Assign { tag, group } => {
+                        manager.set_tag_group(tag, Some(group))?;
+                    }
+                    TagGroupCommands::Unassign { tag } => {
+                        manager.set_tag_group(tag, None)?;
+                    }
+                    TagGroupCommands::Delete { name } => {
+                        manager.delete_tag_group(name)?;
+                    }
+                },
             }
         },
         Commands::Session(cmd) => {
             let mut manager = SessionManager::new(&mut storage);
             match cmd {
-                SessionCommands::Start { title, category, tags, duration } => {
-                    manager.start_session(title, category, tags, duration)?;
+                SessionCommands::Start { title, category, tags, duration, non_billable } => {
+                    manager.start_session(title, category, tags, duration, non_billable)?;
+                }
+                SessionCommands::Add { title, category, tags, duration, start, non_billable } => {
+                    manager.add_session(title, category, tags, start, duration, non_billable)?;
                 }
                 SessionCommands::End { id } => {
                     manager.end_session(id)?;
                 }
+                SessionCommands::Edit { id, title, category, tags, clear_tags, start, duration, rate, clear_rate, billable } => {
+                    let changes = SessionChanges { title, category, tags, clear_tags, start, duration, rate, clear_rate, billable };
+                    manager.edit_session(id, changes)?;
+                }
                 SessionCommands::List => {
                     manager.list_sessions()?;
                 }
@@ -170,15 +849,163 @@ fn main() -> std::result::Result<(), Box<dyn std::error::Error>> {
         },
         Commands::Analysis(args) => {
             let manager = AnalysisManager::new(&storage);
-            manager.generate_analysis(args.period, args.category)?;
+            manager.generate_analysis(&utils::DateRange::from(args.range).or_period("week"), args.category)?;
+        },
+        Commands::Report(args) => {
+            let manager = ReportManager::new(&storage);
+            let range = utils::DateRange::from(args.range).or_period("week");
+            manager.generate(&range, args.category.as_deref(), args.timezone, args.format, args.template.as_deref(), args.output.as_deref())?;
+        },
+        Commands::Invoice(args) => {
+            let manager = InvoiceManager::new(&storage);
+            let range = match args.month {
+                Some(month) => utils::DateRange::month(month),
+                None => utils::DateRange::from(args.range).or_period("month"),
+            };
+            let options = InvoiceOptions { per_session: args.per_session, tax_percent: args.tax, timezone: args.timezone };
+            manager.generate(&args.client, &range, &options, args.format, args.output.as_deref())?;
+        },
+        Commands::Heatmap { month, year, category } => {
+            let manager = AnalysisManager::new(&storage);
+            let (first, last) = match (month, year) {
+                (Some(month), _) => {
+                    let range = utils::DateRange::month(month);
+                    (month, range.to.unwrap_or(month))
+                }
+                (None, year) => {
+                    let year = year.unwrap_or_else(|| Utc::now().year());
+                    let first = NaiveDate::from_ymd_opt(year, 1, 1).ok_or("invalid year")?;
+                    (first, NaiveDate::from_ymd_opt(year, 12, 31).ok_or("invalid year")?)
+                }
+            };
+            manager.show_heatmap(first, last, category)?;
+        },
+        Commands::Status => {
+            let manager = AnalysisManager::new(&storage);
+            manager.show_status()?;
+        },
+        Commands::Compliance { period, timezone } => {
+            let manager = ComplianceManager::new(&storage);
+            manager.generate_report(period, timezone)?;
+        },
+        Commands::Doctor { fix } => {
+            let mut manager = DoctorManager::new(&mut storage);
+            manager.run(fix)?;
+        },
+        Commands::SetQuota { quota, scale, clear: _ } => {
+            let mut manager = QuotaManager::new(&mut storage);
+            match quota {
+                Some(quota) => manager.set_total_quota(quota, scale)?,
+                None => manager.clear_total_quota()?,
+            }
+        },
+        Commands::Config(cmd) => {
+            let mut manager = ConfigManager::new(&mut storage);
+            match cmd {
+                ConfigCommands::Show => {
+                    manager.show_config()?;
+                }
+                ConfigCommands::QuotaPolicy { policy } => {
+                    manager.set_quota_policy(policy)?;
+                }
+                ConfigCommands::TagLimit { limit, off: _ } => {
+                    manager.set_tag_limit(limit)?;
+                }
+                ConfigCommands::RequireTag { group, off } => {
+                    manager.set_tag_requirement(group, off)?;
+                }
+                ConfigCommands::Timeblock { block, rounding } => {
+                    manager.set_timeblock(block, rounding)?;
+                }
+                ConfigCommands::Compliance { reset, max_daily, breaks, break_segment, rest, warn } => {
+                    manager.set_compliance(reset, max_daily, breaks, break_segment, rest, warn)?;
+                }
+            }
+        },
+        Commands::Export(cmd) => {
+            let manager = ExportManager::new(&storage);
+            match cmd {
+                ExportCommands::Csv { range, csv, output } => {
+                    manager.export_csv(&range.into(), &csv.into_options()?, output.as_deref())?;
+                }
+                ExportCommands::Ics { range, output } => {
+                    manager.export_ics(&range.into(), output.as_deref())?;
+                }
+                ExportCommands::Timeclock { range, timezone, output } => {
+                    manager.export_timeclock(&range.into(), timezone, output.as_deref())?;
+                }
+                ExportCommands::Org { range, timezone, output } => {
+                    manager.export_org(&range.into(), timezone, output.as_deref())?;
+                }
+                ExportCommands::Timewarrior { range, output } => {
+                    manager.export_timewarrior(&range.into(), output.as_deref())?;
+                }
+            }
+        },
+        Commands::Import(cmd) => {
+            let data = storage.get_data();
+            let categories: Vec<String> = data.categories.iter().map(|c| data.category_path(&c.id)).collect();
+            let mut manager = ImportManager::new(&mut storage);
+            match cmd {
+                ImportCommands::Csv { file, csv, import } => {
+                    let batch = formats::csv::read(open_import(&file)?, &csv.into_options()?)
+                        .map_err(models::MetronError::ImportError)?;
+                    manager.import(&file.display().to_string(), batch, &import.into())?;
+                }
+                ImportCommands::Ics { file, range, map_title, map_calendar, default_category, timezone, import } => {
+                    let contents = read_import(&file)?;
+                    let options = formats::ics::IcsOptions {
+                        timezone,
+                        title_rules: map_title,
+                        calendar_rules: map_calendar,
+                        default_category,
+                    };
+                    let batch = formats::ics::read(&contents, &options, &range.into(), Utc::now())
+                        .map_err(models::MetronError::ImportError)?;
+                    manager.import(&file.display().to_string(), batch, &import.into())?;
+                }
+                ImportCommands::Timewarrior { path, map_tag, default_category, import } => {
+                    let options = formats::timewarrior::TimewarriorOptions { default_category, tag_rules: map_tag, categories };
+                    let batch = formats::timewarrior::read_path(&path, &options)
+                        .map_err(models::MetronError::ImportError)?;
+                    manager.import(&path.display().to_string(), batch, &import.into())?;
+                }
+                ImportCommands::Watson { file, import } => {
+                    let contents = read_import(&file)?;
+                    let batch = formats::watson::read(&contents).map_err(models::MetronError::ImportError)?;
+                    manager.import(&file.display().to_string(), batch, &import.into())?;
+                }
+                ImportCommands::Toggl { file, tracker, import } => {
+                    let options = tracker.into_options(NaiveTime::MIN);
+                    let batch = formats::trackers::read(open_import(&file)?, Tracker::Toggl, &options)
+                        .map_err(models::MetronError::ImportError)?;
+                    manager.import(&file.display().to_string(), batch, &import.into())?;
+                }
+                ImportCommands::Clockify { file, tracker, import } => {
+                    let options = tracker.into_options(NaiveTime::MIN);
+                    let batch = formats::trackers::read(open_import(&file)?, Tracker::Clockify, &options)
+                        .map_err(models::MetronError::ImportError)?;
+                    manager.import(&file.display().to_string(), batch, &import.into())?;
+                }
+                ImportCommands::Harvest { file, tracker, day_start, import } => {
+                    let options = tracker.into_options(day_start);
+                    let batch = formats::trackers::read(open_import(&file)?, Tracker::Harvest, &options)
+                        .map_err(models::MetronError::ImportError)?;
+                    manager.import(&file.display().to_string(), batch, &import.into())?;
+                }
+            }
         },
-        Commands::SetQuota { hours } => {
-            let data = storage.get_data_mut();
-            data.total_weekly_quota = Some(hours);
-            storage.save()?;
-            println!("✓ Set total weekly quota to {}h", hours);
-        }
     }
 
     Ok(())
 }
+
+fn open_import(file: &std::path::Path) -> models::Result<std::fs::File> {
+    std::fs::File::open(file)
+        .map_err(|e| models::MetronError::ImportError(format!("{}: {}", file.display(), e)))
+}
+
+fn read_import(file: &std::path::Path) -> models::Result<String> {
+    std::fs::read_to_string(file)
+        .map_err(|e| models::MetronError::ImportError(format!("{}: {}", file.display(), e)))
+}
@@ -1,7 +1,16 @@
-use crate::models::Result;
+use crate::models::{MetronData, MetronError, Result, Session};
 use crate::storage::Storage;
-use chrono::{DateTime, Utc, Duration, Datelike};
-use std::collections::HashMap;
+use crate::utils::{format_duration, paint, week_bounds, DateRange};
+use chrono::{DateTime, Datelike, NaiveDate, Utc, Duration};
+use std::collections::{BTreeMap, HashMap};
+
+/// Ranges up to this many days are broken down by day, longer ones by week
+const MAX_DAILY_DAYS: i64 = 31;
+
+/// Heatmap levels: no time, then below each of these minutes, then the rest
+const HEAT_LEVELS: [u32; 3] = [120, 240, 360];
+const HEAT_SYMBOLS: [&str; 5] = ["·", "░", "▒", "▓", "█"];
+const HEAT_COLORS: [Option<&str>; 5] = [None, Some("#9be9a8"), Some("#40c463"), Some("#30a14e"), Some("#216e39")];
 
 pub struct AnalysisManager<'a> {
     storage: &'a Storage,
@@ -12,7 +21,7 @@ impl<'a> AnalysisManager<'a> {
         Self { storage }
     }
 
-    pub fn generate_analysis(&self, period: String, category_filter: Option<String>) -> Result<()> {
+    pub fn generate_analysis(&self, range: &DateRange, category_filter: Option<String>) -> Result<()> {
         let data = self.storage.get_data();
         
         if data.sessions.is_empty() {
@@ -20,12 +29,18 @@ impl<'a> AnalysisManager<'a> {
             return Ok(());
         }
 
+        // Unknown names are compared as-is so sessions with dangling references can still be found
+        let filter_root = category_filter.as_ref().map(|name| {
+            data.find_category(name).map(|c| c.id.clone()).unwrap_or_else(|| name.clone())
+        });
+        let filter_ids = filter_root.as_ref().map(|id| data.subtree_ids(id));
+
         let now = Utc::now();
         let filtered_sessions: Vec<_> = data.sessions.iter()
-            .filter(|s| self.is_in_period(s.start, &period, now))
+            .filter(|s| range.contains(s.start, now))
             .filter(|s| {
-                if let Some(ref cat_filter) = category_filter {
-                    &s.category == cat_filter
+                if let Some(ref ids) = filter_ids {
+                    ids.contains(&s.category_id)
                 } else {
                     true
                 }
@@ -37,103 +52,342 @@ impl<'a> AnalysisManager<'a> {
             return Ok(());
         }
 
-        // Group sessions by category
-        let mut by_category: HashMap<String, Vec<_>> = HashMap::new();
+        // Roll every session up to its category and all of that category's parents
+        let mut by_category: HashMap<String, Vec<&Session>> = HashMap::new();
         for session in &filtered_sessions {
-            by_category.entry(session.category.clone()).or_default().push(session);
+            let chain = data.ancestors(&session.category_id);
+            if chain.is_empty() {
+                by_category.entry(session.category_id.clone()).or_default().push(session);
+            }
+            for category in chain {
+                by_category.entry(category.id.clone()).or_default().push(session);
+            }
         }
 
-        println!("📊 Analysis Report - {}", period.to_uppercase());
+        println!("📊 Analysis Report - {}", range.to_string().to_uppercase());
         if let Some(ref cat) = category_filter {
             println!("   Category: {}", cat);
         }
         println!("{}", "=".repeat(60));
 
-        let mut total_work_time = 0u32;
-        let mut total_overtime = 0u32;
+        // Categories in tree order, followed by IDs that no longer match a category
+        let mut nodes: Vec<(String, usize)> = data.category_tree().into_iter()
+            .filter(|(c, _)| by_category.contains_key(&c.id))
+            .map(|(c, depth)| (c.id.clone(), depth))
+            .collect();
+        let mut dangling: Vec<String> = by_category.keys()
+            .filter(|id| data.category(id).is_none())
+            .cloned()
+            .collect();
+        dangling.sort();
+        nodes.extend(dangling.into_iter().map(|id| (id, 0)));
+
+        // With a filter, show the filtered category as the top of the tree
+        let base_depth = filter_root.as_ref()
+            .and_then(|id| nodes.iter().find(|(n, _)| n == id))
+            .map(|(_, depth)| *depth)
+            .unwrap_or(0);
+        let roots: Vec<&String> = nodes.iter()
+            .filter(|(_, depth)| *depth == base_depth)
+            .filter(|(id, _)| filter_ids.as_ref().is_none_or(|ids| ids.contains(id)))
+            .map(|(id, _)| id)
+            .collect();
 
-        for (category_name, sessions) in &by_category {
-            let category = data.categories.iter().find(|c| &c.name == category_name);
-            let weekly_quota = category.map(|c| c.category_weekly_quota).unwrap_or(0);
-            
+        for (category_id, depth) in &nodes {
+            if filter_ids.as_ref().is_some_and(|ids| !ids.contains(category_id)) {
+                continue;
+            }
+            let sessions = &by_category[category_id];
+            let indent = "   ".repeat(depth - base_depth);
+            let category = data.category(category_id);
+            let quota_minutes = category.map(|c| c.category_weekly_quota).unwrap_or(0);
             let total_minutes: u32 = sessions.iter().map(|s| s.duration).sum();
-            let total_hours = total_minutes as f64 / 60.0;
-            
-            // Calculate work time vs overtime based on weekly quota
-            let quota_minutes = weekly_quota * 60;
-            let work_time_minutes = if quota_minutes > 0 {
-                std::cmp::min(total_minutes, quota_minutes)
-            } else {
-                total_minutes
-            };
-            let overtime_minutes = if total_minutes > quota_minutes && quota_minutes > 0 {
-                total_minutes - quota_minutes
-            } else {
-                0
-            };
-
-            total_work_time += work_time_minutes;
-            total_overtime += overtime_minutes;
-
-            println!("\n📁 Category: {}", category_name);
-            println!("   Sessions: {}", sessions.len());
-            println!("   Total Time: {:.1}h ({} minutes)", total_hours, total_minutes);
+
+            let path = data.category_path(category_id);
+            println!("\n{}📁 Category: {}", indent, paint(&path, data.category_color(category_id)));
+            if let Some(description) = category.and_then(|c| c.description.as_ref()) {
+                println!("{}   {}", indent, description);
+            }
+            println!("{}   Sessions: {}", indent, sessions.len());
+            println!("{}   Total Time: {}", indent, format_duration(total_minutes));
             if quota_minutes > 0 {
-                println!("   Weekly Quota: {}h", weekly_quota);
-                println!("   Work Time: {:.1}h ({} minutes)", work_time_minutes as f64 / 60.0, work_time_minutes);
+                let overtime_minutes = total_minutes.saturating_sub(quota_minutes);
+                println!("{}   Weekly Quota: {}", indent, format_duration(quota_minutes));
+                println!("{}   Work Time: {}", indent, format_duration(total_minutes - overtime_minutes));
                 if overtime_minutes > 0 {
-                    println!("   Overtime: {:.1}h ({} minutes)", overtime_minutes as f64 / 60.0, overtime_minutes);
+                    println!("{}   Overtime: {}", indent, format_duration(overtime_minutes));
                 }
             }
-            
+
+            if let Some(target) = category.and_then(|c| c.category_weekly_target) {
+                if range.is_week() {
+                    print!("{}", indent);
+                    self.print_target_progress(total_minutes, target, now);
+                }
+            }
+
             // Show tag breakdown
-            let mut tag_minutes: HashMap<String, u32> = HashMap::new();
+            let mut tag_minutes: HashMap<&str, u32> = HashMap::new();
             for session in sessions {
-                for tag in &session.tags {
-                    *tag_minutes.entry(tag.clone()).or_default() += session.duration;
+                for tag_id in &session.tag_ids {
+                    *tag_minutes.entry(tag_id.as_str()).or_default() += session.duration;
                 }
             }
-            
+
             if !tag_minutes.is_empty() {
-                println!("   Tags:");
-                for (tag, minutes) in &tag_minutes {
-                    println!("     - {}: {:.1}h", tag, *minutes as f64 / 60.0);
+                println!("{}   Tags:", indent);
+                for (tag_id, minutes) in &tag_minutes {
+                    let tag = paint(data.tag_name(tag_id), data.tag_color(tag_id));
+                    println!("{}     - {}: {}", indent, tag, format_duration(*minutes));
                 }
             }
         }
 
+        let (first, last) = range.bounds(now);
+        let dates = filtered_sessions.iter().map(|s| s.start.date_naive());
+        let first = first.or_else(|| dates.clone().min()).unwrap_or(now.date_naive());
+        let last = last.or_else(|| dates.max()).unwrap_or(now.date_naive());
+        self.print_breakdown(data, &roots, &by_category, first, last);
+
+        let grand_total: u32 = filtered_sessions.iter().map(|s| s.duration).sum();
+        let total_overtime: u32 = roots.iter().map(|id| overtime(data, id, &by_category)).sum();
+
         println!("\n{}", "=".repeat(60));
         println!("📈 SUMMARY");
-        println!("   Total Work Time: {:.1}h ({} minutes)", total_work_time as f64 / 60.0, total_work_time);
+        println!("   Total Work Time: {}", format_duration(grand_total - total_overtime));
         if total_overtime > 0 {
-            println!("   Total Overtime: {:.1}h ({} minutes)", total_overtime as f64 / 60.0, total_overtime);
+            println!("   Total Overtime: {}", format_duration(total_overtime));
         }
-        println!("   Grand Total: {:.1}h ({} minutes)", (total_work_time + total_overtime) as f64 / 60.0, total_work_time + total_overtime);
+        println!("   Grand Total: {}", format_duration(grand_total));
         println!("   Sessions: {}", filtered_sessions.len());
 
         Ok(())
     }
 
-    fn is_in_period(&self, session_start: DateTime<Utc>, period: &str, now: DateTime<Utc>) -> bool {
-        match period.to_lowercase().as_str() {
-            "day" | "daily" => {
-                session_start.date_naive() == now.date_naive()
-            },
-            "week" | "weekly" => {
-                let week_start = now - Duration::days(now.weekday().num_days_from_monday() as i64);
-                session_start >= week_start && session_start <= now
-            },
-            "month" | "monthly" => {
-                session_start.year() == now.year() && session_start.month() == now.month()
-            },
-            "year" | "yearly" => {
-                session_start.year() == now.year()
-            },
-            _ => {
-                eprintln!("Unknown period '{}', using weekly", period);
-                let week_start = now - Duration::days(now.weekday().num_days_from_monday() as i64);
-                session_start >= week_start && session_start <= now
+    /// Prints time per day, or per week for long ranges, split by the top-level categories of the analysis
+    fn print_breakdown(
+        &self,
+        data: &MetronData,
+        roots: &[&String],
+        by_category: &HashMap<String, Vec<&Session>>,
+        first: NaiveDate,
+        last: NaiveDate,
+    ) {
+        let daily = (last - first).num_days() < MAX_DAILY_DAYS;
+        let period_of = |date: NaiveDate| {
+            if daily { date } else { date - Duration::days(date.weekday().num_days_from_monday() as i64) }
+        };
+
+        // Every day or week of the range, so gaps show up as rows of dashes
+        let mut rows: BTreeMap<NaiveDate, Vec<u32>> = BTreeMap::new();
+        for date in first.iter_days().take_while(|d| *d <= last) {
+            rows.entry(period_of(date)).or_insert_with(|| vec![0; roots.len()]);
+        }
+        for (column, root) in roots.iter().enumerate() {
+            for session in &by_category[root.as_str()] {
+                let row = rows.entry(period_of(session.start.date_naive())).or_insert_with(|| vec![0; roots.len()]);
+                row[column] += session.duration;
+            }
+        }
+
+        let names: Vec<String> = roots.iter().map(|id| data.category_path(id)).collect();
+        let widths: Vec<usize> = names.iter().map(|n| n.chars().count().max(8)).collect();
+        let amount = |minutes: u32| if minutes == 0 { "-".to_string() } else { format_duration(minutes) };
+        let label_of = |start: &NaiveDate| {
+            if daily { start.format("%a %Y-%m-%d").to_string() } else { start.format("%G-W%V %b %d").to_string() }
+        };
+        let label_width = rows.keys().map(|start| label_of(start).chars().count()).max().unwrap_or(0);
+
+        println!("\n{}", "=".repeat(60));
+        println!("{}", if daily { "📅 DAILY BREAKDOWN" } else { "📅 WEEKLY BREAKDOWN" });
+        let mut header = format!("   {:<label_width$}", if daily { "Day" } else { "Week" });
+        for ((name, width), id) in names.iter().zip(&widths).zip(roots) {
+            header.push_str(&format!(" {}", paint(&format!("{:>width$}", name), data.category_color(id))));
+        }
+        println!("{}  {:>8}", header, "Total");
+        for (start, minutes) in &rows {
+            let mut line = format!("   {:<label_width$}", label_of(start));
+            for (value, width) in minutes.iter().zip(&widths) {
+                line.push_str(&format!(" {:>width$}", amount(*value)));
+            }
+            println!("{}  {:>8}", line, amount(minutes.iter().sum()));
+        }
+    }
+
+    /// Prints a calendar of tracked time per day between `first` and `last`,
+    /// with a column per week like a contributions graph
+    pub fn show_heatmap(&self, first: NaiveDate, last: NaiveDate, category_filter: Option<String>) -> Result<()> {
+        let data = self.storage.get_data();
+
+        let filter_ids = match &category_filter {
+            Some(name) => Some(data.subtree_ids(&data.find_category(name).ok_or(MetronError::CategoryNotFound)?.id)),
+            None => None,
+        };
+        let mut minutes: HashMap<NaiveDate, u32> = HashMap::new();
+        for session in &data.sessions {
+            let date = session.start.date_naive();
+            if date >= first && date <= last && filter_ids.as_ref().is_none_or(|ids| ids.contains(&session.category_id)) {
+                *minutes.entry(date).or_default() += session.duration;
+            }
+        }
+
+        let today = Utc::now().date_naive();
+        let monday = first - Duration::days(first.weekday().num_days_from_monday() as i64);
+        let weeks = ((last - monday).num_days() / 7 + 1) as usize;
+        // A month has room for the date of each week's Monday, a year only for one character per day
+        let wide = weeks <= 10;
+
+        let title = if wide { first.format("%B %Y").to_string() } else if first.year() == last.year() {
+            first.year().to_string()
+        } else {
+            format!("{} to {}", first, last)
+        };
+        match &category_filter {
+            Some(category) => println!("🗓  {} – {}", title, category),
+            None => println!("🗓  {}", title),
+        }
+
+        // Above the weeks: the day of their Monday, or the name of the month starting in them
+        let mut labels = String::from("   ");
+        for week in 0..weeks {
+            let week_start = monday + Duration::days(7 * week as i64);
+            if wide {
+                labels.push_str(&format!("{:>3}", week_start.day()));
+                continue;
+            }
+            let starts_month = (0..7)
+                .map(|d| week_start + Duration::days(d))
+                .find(|d| d.day() == 1 && *d >= first && *d <= last);
+            let column = 4 + week;
+            if let Some(date) = starts_month.or_else(|| (week == 0).then_some(first)) {
+                if labels.chars().count() <= column {
+                    labels.push_str(&" ".repeat(column - labels.chars().count()));
+                    labels.push_str(&date.format("%b").to_string());
+                }
+            }
+        }
+        println!("{}", labels);
+
+        for weekday in 0..7 {
+            let mut line = ["Mon", "Tue", "Wed", "Thu", "Fri", "Sat", "Sun"][weekday].to_string();
+            if !wide {
+                line.push(' ');
+            }
+            for week in 0..weeks {
+                let date = monday + Duration::days((7 * week + weekday) as i64);
+                let cell = if date < first || date > last || date > today {
+                    " ".to_string()
+                } else {
+                    let level = heat_level(minutes.get(&date).copied().unwrap_or(0));
+                    paint(HEAT_SYMBOLS[level], HEAT_COLORS[level])
+                };
+                if wide {
+                    line.push_str("  ");
+                }
+                line.push_str(&cell);
+            }
+            println!("{}", line.trim_end());
+        }
+
+        let legend: Vec<String> = HEAT_SYMBOLS.iter().zip(HEAT_COLORS).enumerate()
+            .map(|(level, (symbol, color))| {
+                let text = match level {
+                    0 => "none".to_string(),
+                    l if l <= HEAT_LEVELS.len() => format!("under {}", format_duration(HEAT_LEVELS[l - 1])),
+                    _ => format!("{} or more", format_duration(HEAT_LEVELS[HEAT_LEVELS.len() - 1])),
+                };
+                format!("{} {}", paint(symbol, color), text)
+            })
+            .collect();
+        println!("\n    {}", legend.join("  "));
+
+        let total: u32 = minutes.values().sum();
+        if let Some((date, most)) = minutes.iter().max_by_key(|(date, m)| (**m, std::cmp::Reverse(**date))) {
+            let days = if minutes.len() == 1 { "day" } else { "days" };
+            println!("    {} on {} {}, most on {} ({})",
+                format_duration(total), minutes.len(), days, date.format("%a %Y-%m-%d"), format_duration(*most));
+        } else {
+            println!("    No time tracked.");
+        }
+
+        Ok(())
+    }
+
+    /// Shows this week's progress of every category toward its quota and target
+    pub fn show_status(&self) -> Result<()> {
+        let data = self.storage.get_data();
+
+        if data.categories.is_empty() {
+            println!("No categories found.");
+            return Ok(());
+        }
+
+        let now = Utc::now();
+        let (week_start, _) = week_bounds(now);
+
+        println!("📅 Week of {}", week_start.format("%Y-%m-%d"));
+        println!("{}", "=".repeat(60));
+
+        for (category, depth) in data.category_tree().into_iter().filter(|(c, _)| !c.archived) {
+            let minutes = data.category_minutes_in_week(&category.id, now);
+            let indent = "   ".repeat(depth);
+
+            println!("\n{}📁 {}", indent, paint(&category.name, data.category_color(&category.id)));
+            println!("{}   Logged: {}", indent, format_duration(minutes));
+            if category.category_weekly_quota > 0 {
+                let quota_minutes = category.category_weekly_quota;
+                if minutes > quota_minutes {
+                    println!("{}   Quota: {} (exceeded by {})", indent, format_duration(quota_minutes), format_duration(minutes - quota_minutes));
+                } else {
+                    println!("{}   Quota: {} ({} remaining)", indent, format_duration(quota_minutes), format_duration(quota_minutes - minutes));
+                }
+            }
+            if let Some(target) = category.category_weekly_target {
+                print!("{}", indent);
+                self.print_target_progress(minutes, target, now);
             }
         }
+
+        Ok(())
+    }
+
+    /// Prints progress toward a weekly target, comparing against the share of the week already passed
+    fn print_target_progress(&self, minutes: u32, target: u32, now: DateTime<Utc>) {
+        let target_minutes = target;
+        let (week_start, _) = week_bounds(now);
+        let elapsed = (now - week_start).num_minutes() as f64 / Duration::days(7).num_minutes() as f64;
+        let expected = (target_minutes as f64 * elapsed.min(1.0)) as u32;
+
+        if minutes >= target_minutes {
+            println!("   Target: {} ✓ reached", format_duration(target_minutes));
+        } else {
+            let status = if minutes >= expected { "on track" } else { "behind" };
+            println!("   Target: {} ({}), {} still needed this week",
+                format_duration(target_minutes), status, format_duration(target_minutes - minutes));
+        }
+    }
+}
+
+/// Time logged beyond the quota of a category, or beyond the quotas of its
+/// subcategories if it has none of its own
+pub(crate) fn overtime(data: &MetronData, id: &str, by_category: &HashMap<String, Vec<&Session>>) -> u32 {
+    let Some(sessions) = by_category.get(id) else { return 0 };
+    match data.category(id) {
+        Some(category) if category.category_weekly_quota > 0 => {
+            let total: u32 = sessions.iter().map(|s| s.duration).sum();
+            total.saturating_sub(category.category_weekly_quota)
+        }
+        Some(category) => data.children(Some(&category.id))
+            .map(|child| overtime(data, &child.id, by_category))
+            .sum(),
+        None => 0,
+    }
+}
+
+/// Index into `HEAT_SYMBOLS` for the minutes of a day
+fn heat_level(minutes: u32) -> usize {
+    if minutes == 0 {
+        return 0;
     }
+    1 + HEAT_LEVELS.iter().take_while(|limit| minutes >= **limit).count()
 }
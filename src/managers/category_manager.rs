@@ -1,5 +1,7 @@
-use crate::models::{Category, MetronError, Result};
+use crate::models::{check_quota_tree, Category, MergeRule, MetronError, Rate, Result, Rounding};
 use crate::storage::Storage;
+use crate::utils::{format_duration, non_empty, paint};
+use uuid::Uuid;
 
 pub struct CategoryManager<'a> {
     storage: &'a mut Storage,
@@ -10,120 +12,427 @@ impl<'a> CategoryManager<'a> {
         Self { storage }
     }
 
-    pub fn create_category(&mut self, name: String, quota: u32) -> Result<()> {
+    /// Creates a category; a path like `Acme/Website` creates it below an existing parent
+    pub fn create_category(&mut self, path: String, quota: u32, target: Option<u32>, description: Option<String>, color: Option<String>) -> Result<()> {
         let data = self.storage.get_data_mut();
-        
-        // Check if category already exists
-        if data.categories.iter().any(|c| c.name == name) {
-            return Err(MetronError::DuplicateName);
+
+        let (parent_path, name) = match path.rsplit_once('/') {
+            Some((parent, name)) => (Some(parent), name.trim()),
+            None => (None, path.trim()),
+        };
+        if name.is_empty() {
+            return Err(MetronError::InvalidName);
         }
 
-        // Check if adding this quota would exceed total weekly quota
-        if let Some(total_quota) = data.total_weekly_quota {
-            let current_total: u32 = data.categories.iter().map(|c| c.category_weekly_quota).sum();
-            if current_total + quota > total_quota {
-                return Err(MetronError::QuotaExceeded);
+        let parent_id = match parent_path {
+            Some(parent_path) => {
+                let parent = data.find_category(parent_path).ok_or(MetronError::CategoryNotFound)?;
+                if parent.archived {
+                    return Err(MetronError::CategoryArchived);
+                }
+                Some(parent.id.clone())
             }
+            None => None,
+        };
+
+        // Check if category already exists
+        if data.child_named(parent_id.as_deref(), name).is_some() {
+            return Err(MetronError::DuplicateName);
         }
 
         let category = Category {
-            name: name.clone(),
+            id: Uuid::new_v4().to_string(),
+            name: name.to_string(),
             category_weekly_quota: quota,
+            category_weekly_target: target.filter(|t| *t > 0),
+            block_minutes: None,
+            rounding: None,
+            archived: false,
+            parent_id,
+            tag_requirement: None,
+            description: description.and_then(non_empty),
+            color: color.and_then(non_empty),
+            rate: None,
         };
 
-        data.categories.push(category);
+        // Check if adding this quota would exceed the parent's or the total weekly quota
+        let mut categories = data.categories.clone();
+        categories.push(category.clone());
+        check_quota_tree(&categories, data.total_weekly_quota)?;
+
+        data.categories = categories;
+        let path = data.category_path(&category.id);
         self.storage.save()?;
 
-        println!("✓ Created category '{}' with {}h/week quota", name, quota);
+        println!("✓ Created category '{}' with {}/week quota", path, format_duration(quota));
+        if let Some(target) = target.filter(|t| *t > 0) {
+            println!("  Weekly target: {}", format_duration(target));
+        }
         Ok(())
     }
 
-    pub fn list_categories(&self) -> Result<()> {
+    /// Lists categories as a tree; archived ones are only shown with `all`
+    pub fn list_categories(&self, all: bool) -> Result<()> {
         let data = self.storage.get_data();
-        
-        if data.categories.is_empty() {
+        let categories: Vec<_> = data.category_tree().into_iter()
+            .filter(|(c, _)| all || !c.archived)
+            .collect();
+
+        if categories.is_empty() {
             println!("No categories found.");
             return Ok(());
         }
 
         println!("Categories:");
-        println!("{:<20} {:<15}", "Name", "Weekly Quota");
-        println!("{}", "-".repeat(35));
-        
-        for category in &data.categories {
-            println!("{:<20} {:<15}h", category.name, category.category_weekly_quota);
+        println!("{:<30} {:<15} {:<15} {:<15} Description", "Name", "Weekly Quota", "Weekly Target", "Rate");
+        println!("{}", "-".repeat(60));
+
+        for (category, depth) in categories {
+            let name = format!("{}{}", "  ".repeat(depth), category.name);
+            let quota = if category.category_weekly_quota > 0 {
+                format_duration(category.category_weekly_quota)
+            } else {
+                "-".to_string()
+            };
+            let target = category.category_weekly_target
+                .map(format_duration)
+                .unwrap_or_else(|| "-".to_string());
+            let rate = category.rate.as_ref()
+                .map(|r| r.to_string())
+                .unwrap_or_else(|| "-".to_string());
+            let description = category.description.as_deref().unwrap_or("");
+            let archived = if category.archived { " (archived)" } else { "" };
+            let name = paint(&format!("{:<30}", name), data.category_color(&category.id));
+            println!("{} {:<15} {:<15} {:<15} {}{}", name, quota, target, rate, description, archived);
         }
 
         // Show total quota info
-        let total_used: u32 = data.categories.iter().map(|c| c.category_weekly_quota).sum();
+        let total_used = data.committed_quota();
         if let Some(total_quota) = data.total_weekly_quota {
-            println!("{}", "-".repeat(35));
-            println!("Total used: {}h / {}h", total_used, total_quota);
+            println!("{}", "-".repeat(60));
+            println!("Total used: {} / {}", format_duration(total_used), format_duration(total_quota));
         } else {
-            println!("{}", "-".repeat(35));
-            println!("Total used: {}h (no total quota set)", total_used);
+            println!("{}", "-".repeat(60));
+            println!("Total used: {} (no total quota set)", format_duration(total_used));
         }
 
         Ok(())
     }
 
-    pub fn update_category(&mut self, name: String, quota: u32) -> Result<()> {
+    /// Changes a category's quota, target, description or color; empty values remove the last two
+    pub fn update_category(
+        &mut self,
+        name: String,
+        quota: Option<u32>,
+        target: Option<u32>,
+        description: Option<String>,
+        color: Option<String>,
+    ) -> Result<()> {
         let data = self.storage.get_data_mut();
-        
-        // Check quota limits first
-        if let Some(total_quota) = data.total_weekly_quota {
-            let other_quotas: u32 = data.categories.iter()
-                .filter(|c| c.name != name)
-                .map(|c| c.category_weekly_quota)
-                .sum();
-            
-            if other_quotas + quota > total_quota {
-                return Err(MetronError::QuotaExceeded);
+
+        let id = data.find_category(&name)
+            .map(|c| c.id.clone())
+            .ok_or(MetronError::CategoryNotFound)?;
+
+        let mut categories = data.categories.clone();
+        let category = categories.iter_mut()
+            .find(|c| c.id == id)
+            .ok_or(MetronError::CategoryNotFound)?;
+
+        let old_quota = category.category_weekly_quota;
+        let old_target = category.category_weekly_target;
+        if let Some(quota) = quota {
+            category.category_weekly_quota = quota;
+        }
+        if let Some(target) = target {
+            // A target of 0 removes it
+            category.category_weekly_target = Some(target).filter(|t| *t > 0);
+        }
+        let new_target = category.category_weekly_target;
+        if let Some(description) = description.clone() {
+            category.description = non_empty(description);
+        }
+        if let Some(color) = color.clone() {
+            category.color = non_empty(color);
+        }
+
+        // Check quota limits before committing
+        check_quota_tree(&categories, data.total_weekly_quota)?;
+
+        data.categories = categories;
+        let path = data.category_path(&id);
+        self.storage.save()?;
+
+        if let Some(quota) = quota {
+            println!("✓ Updated category '{}' quota: {} → {}", path, format_duration(old_quota), format_duration(quota));
+        }
+        if target.is_some() {
+            let show = |t: Option<u32>| t.map(format_duration).unwrap_or_else(|| "none".to_string());
+            println!("✓ Updated category '{}' target: {} → {}", path, show(old_target), show(new_target));
+        }
+        if description.is_some() {
+            println!("✓ Updated category '{}' description", path);
+        }
+        if let Some(color) = color {
+            let color = non_empty(color);
+            let shown = color.as_deref().map(|c| paint(c, Some(c))).unwrap_or_else(|| "none".to_string());
+            println!("✓ Updated category '{}' color: {}", path, shown);
+        }
+        Ok(())
+    }
+
+    /// Renames a category; sessions reference it by ID and follow automatically
+    pub fn rename_category(&mut self, name: String, new_name: String) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let new_name = new_name.trim().to_string();
+        if new_name.is_empty() || new_name.contains('/') {
+            return Err(MetronError::InvalidName);
+        }
+
+        let category = data.find_category(&name).cloned().ok_or(MetronError::CategoryNotFound)?;
+
+        // Changing only the case of the name is allowed
+        if data.child_named(category.parent_id.as_deref(), &new_name).is_some_and(|c| c.id != category.id) {
+            return Err(MetronError::DuplicateName);
+        }
+
+        let old_path = data.category_path(&category.id);
+        if let Some(c) = data.categories.iter_mut().find(|c| c.id == category.id) {
+            c.name = new_name;
+        }
+        let new_path = data.category_path(&category.id);
+
+        let sessions = data.sessions.iter().filter(|s| s.category_id == category.id).count();
+        self.storage.save()?;
+
+        println!("✓ Renamed category '{}' to '{}' ({} sessions)", old_path, new_path, sessions);
+        Ok(())
+    }
+
+    /// Moves all sessions and subcategories of `from` into `into`, combines their
+    /// quotas and targets by `rule` and removes `from`
+    pub fn merge_categories(&mut self, from: String, into: String, rule: MergeRule) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let source = data.find_category(&from).cloned().ok_or(MetronError::CategoryNotFound)?;
+        let target = data.find_category(&into).cloned().ok_or(MetronError::CategoryNotFound)?;
+        if data.subtree_ids(&source.id).contains(&target.id) {
+            return Err(MetronError::MergeIntoSelf);
+        }
+
+        // Subcategories move along and must not clash with the target's own
+        for child in data.children(Some(&source.id)) {
+            if data.child_named(Some(&target.id), &child.name).is_some() {
+                return Err(MetronError::DuplicateName);
+            }
+        }
+
+        let quota = rule.combine(source.category_weekly_quota, target.category_weekly_quota);
+        let weekly_target = match (source.category_weekly_target, target.category_weekly_target) {
+            (Some(a), Some(b)) => Some(rule.combine(a, b)),
+            (a, None) if rule != MergeRule::Keep => a,
+            (_, b) => b,
+        };
+
+        let mut categories = data.categories.clone();
+        for category in categories.iter_mut() {
+            if category.id == target.id {
+                category.category_weekly_quota = quota;
+                category.category_weekly_target = weekly_target;
             }
+            if category.parent_id.as_deref() == Some(source.id.as_str()) {
+                category.parent_id = Some(target.id.clone());
+            }
+        }
+        categories.retain(|c| c.id != source.id);
+
+        // The merged quota must still fit the parent's and the total weekly quota
+        check_quota_tree(&categories, data.total_weekly_quota)?;
+
+        let source_path = data.category_path(&source.id);
+        let target_path = data.category_path(&target.id);
+        let session_count = data.sessions.iter().filter(|s| s.category_id == source.id).count();
+        println!("Merging '{}' into '{}': {} sessions will be moved, quota {} → {}.",
+            source_path, target_path, session_count,
+            format_duration(target.category_weekly_quota), format_duration(quota));
+        println!("'{}' will be removed. Continue? (y/N)", source_path);
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+
+        if !input.trim().to_lowercase().starts_with('y') {
+            println!("Merge cancelled.");
+            return Ok(());
         }
 
-        // Find and update the category
-        let category = data.categories.iter_mut()
-            .find(|c| c.name == name)
+        for session in data.sessions.iter_mut().filter(|s| s.category_id == source.id) {
+            session.category_id = target.id.clone();
+        }
+        data.categories = categories;
+
+        self.storage.save()?;
+
+        println!("✓ Merged category '{}' into '{}' ({} sessions moved)", source_path, target_path, session_count);
+        Ok(())
+    }
+
+    /// Retires a category and its subcategories: they are hidden from `list`, their
+    /// quota no longer counts and no new sessions can be logged, but history stays
+    pub fn archive_category(&mut self, name: String) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let category = data.find_category(&name).cloned().ok_or(MetronError::CategoryNotFound)?;
+        let path = data.category_path(&category.id);
+        if category.archived {
+            println!("Category '{}' is already archived.", path);
+            return Ok(());
+        }
+
+        let ids = data.subtree_ids(&category.id);
+        for c in data.categories.iter_mut().filter(|c| ids.contains(&c.id)) {
+            c.archived = true;
+        }
+
+        self.storage.save()?;
+
+        println!("✓ Archived category '{}' ({} quota freed)", path, format_duration(category.category_weekly_quota));
+        if ids.len() > 1 {
+            println!("  Also archived {} subcategories", ids.len() - 1);
+        }
+        Ok(())
+    }
+
+    /// Reactivates a category and its subcategories
+    pub fn unarchive_category(&mut self, name: String) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let category = data.find_category(&name).cloned().ok_or(MetronError::CategoryNotFound)?;
+        let path = data.category_path(&category.id);
+        if !category.archived {
+            println!("Category '{}' is not archived.", path);
+            return Ok(());
+        }
+        if category.parent_id.as_deref().and_then(|p| data.category(p)).is_some_and(|p| p.archived) {
+            return Err(MetronError::CategoryArchived);
+        }
+
+        // Its quota has to fit into the parent's and the total again
+        let ids = data.subtree_ids(&category.id);
+        let mut categories = data.categories.clone();
+        for c in categories.iter_mut().filter(|c| ids.contains(&c.id)) {
+            c.archived = false;
+        }
+        check_quota_tree(&categories, data.total_weekly_quota)?;
+
+        data.categories = categories;
+        self.storage.save()?;
+
+        println!("✓ Unarchived category '{}'", path);
+        Ok(())
+    }
+
+    /// Overrides the global timeblock for a category and its subcategories;
+    /// `clear` goes back to the inherited settings
+    pub fn set_timeblock(&mut self, name: String, block_minutes: Option<u32>, rounding: Option<Rounding>, clear: bool) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let category = data.find_category_mut(&name)
             .ok_or(MetronError::CategoryNotFound)?;
 
-        let old_quota = category.category_weekly_quota;
-        category.category_weekly_quota = quota;
-        
+        if clear {
+            category.block_minutes = None;
+            category.rounding = None;
+        }
+        if block_minutes.is_some() {
+            category.block_minutes = block_minutes;
+        }
+        if rounding.is_some() {
+            category.rounding = rounding;
+        }
+
+        let id = category.id.clone();
+        let timeblock = data.timeblock_for(&id);
+        let path = data.category_path(&id);
         self.storage.save()?;
 
-        println!("✓ Updated category '{}' quota: {}h → {}h", name, old_quota, quota);
+        println!("✓ Category '{}' now tracks in {}", path, timeblock);
         Ok(())
     }
 
-    pub fn delete_category(&mut self, name: String) -> Result<()> {
+    /// Sets the hourly rate of a category and its subcategories; `None` goes back to inheriting it
+    pub fn set_rate(&mut self, name: String, rate: Option<Rate>) -> Result<()> {
         let data = self.storage.get_data_mut();
-        
-        let index = data.categories.iter()
-            .position(|c| c.name == name)
+
+        let category = data.find_category_mut(&name)
             .ok_or(MetronError::CategoryNotFound)?;
+        category.rate = rate;
+
+        let id = category.id.clone();
+        let rate = data.category_rate(&id);
+        let path = data.category_path(&id);
+        self.storage.save()?;
+
+        match rate {
+            Some(rate) => println!("✓ Sessions in '{}' are now billed at {} per hour", path, rate),
+            None => println!("✓ Sessions in '{}' now have no rate", path),
+        }
+        Ok(())
+    }
+
+    /// Overrides the tag rule for a category and its subcategories; `clear` goes back to inheriting it
+    pub fn set_tag_requirement(&mut self, name: String, group: Option<String>, off: bool, clear: bool) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let requirement = if clear {
+            None
+        } else {
+            Some(data.tag_requirement_from(group.as_deref(), off)?)
+        };
+        let category = data.find_category_mut(&name)
+            .ok_or(MetronError::CategoryNotFound)?;
+        category.tag_requirement = requirement;
+
+        let id = category.id.clone();
+        let rule = data.describe_tag_requirement(data.tag_requirement_for(&id));
+        let path = data.category_path(&id);
+        self.storage.save()?;
+
+        println!("✓ Sessions in '{}' now need {}", path, rule);
+        Ok(())
+    }
+
+    pub fn delete_category(&mut self, name: String) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let category = data.find_category(&name).cloned().ok_or(MetronError::CategoryNotFound)?;
+        let path = data.category_path(&category.id);
+        if data.children(Some(&category.id)).next().is_some() {
+            return Err(MetronError::CategoryHasChildren);
+        }
 
         // Check if any sessions use this category
         let sessions_using_category = data.sessions.iter()
-            .any(|s| s.category == name);
-        
+            .any(|s| s.category_id == category.id);
+
         if sessions_using_category {
-            println!("Warning: Category '{}' is used by existing sessions.", name);
+            println!("Warning: Category '{}' is used by existing sessions.", path);
+            println!("Consider 'metron category archive' to keep their history intact.");
             println!("Delete anyway? (y/N)");
-            
+
             let mut input = String::new();
             std::io::stdin().read_line(&mut input).unwrap();
-            
+
             if !input.trim().to_lowercase().starts_with('y') {
                 println!("Deletion cancelled.");
                 return Ok(());
             }
         }
 
-        data.categories.remove(index);
+        data.categories.retain(|c| c.id != category.id);
         self.storage.save()?;
 
-        println!("✓ Deleted category '{}'", name);
+        println!("✓ Deleted category '{}'", path);
         Ok(())
     }
 }
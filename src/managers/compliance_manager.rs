@@ -0,0 +1,152 @@
+use crate::models::{ComplianceRules, MetronData, Result};
+use crate::storage::Storage;
+use crate::utils::{format_duration, is_in_period, local_now};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use std::collections::BTreeMap;
+
+/// Start, end and worked minutes of one session
+type Interval = (DateTime<Utc>, DateTime<Utc>, u32);
+
+/// Working-time summary of one local calendar day and the rules it breaks
+pub struct DayCompliance {
+    pub date: NaiveDate,
+    pub worked_minutes: u32,
+    pub break_minutes: u32,
+    pub violations: Vec<String>,
+}
+
+pub struct ComplianceManager<'a> {
+    storage: &'a Storage,
+}
+
+impl<'a> ComplianceManager<'a> {
+    pub fn new(storage: &'a Storage) -> Self {
+        Self { storage }
+    }
+
+    /// Checks the days of `period` as they fall in `timezone`
+    pub fn generate_report(&self, period: String, timezone: Tz) -> Result<()> {
+        let data = self.storage.get_data();
+        let rules = &data.settings.compliance;
+        let now = local_now(Utc::now(), timezone);
+
+        let days: Vec<_> = check_days(data, timezone).into_iter()
+            .filter(|d| is_in_period(day_start(d.date), &period, now))
+            .collect();
+
+        println!("⚖️  Working-Time Compliance - {}", period.to_uppercase());
+        println!("   Max {}/day, rest {} between days, breaks: {}",
+            format_duration(rules.max_daily_minutes),
+            format_duration(rules.min_rest_minutes),
+            rules.breaks.iter()
+                .map(|b| format!("{} after {}", format_duration(b.break_minutes), format_duration(b.after_minutes)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        );
+        println!("{}", "=".repeat(60));
+
+        if days.is_empty() {
+            println!("No sessions found for the specified period.");
+            return Ok(());
+        }
+
+        let violating: Vec<_> = days.iter().filter(|d| !d.violations.is_empty()).collect();
+        for day in &violating {
+            println!("\n✗ {} — worked {}, breaks {}",
+                day.date.format("%a %Y-%m-%d"),
+                format_duration(day.worked_minutes),
+                format_duration(day.break_minutes)
+            );
+            for violation in &day.violations {
+                println!("   - {}", violation);
+            }
+        }
+
+        println!("\n{}", "=".repeat(60));
+        if violating.is_empty() {
+            println!("✓ All {} tracked days comply", days.len());
+        } else {
+            println!("{} of {} tracked days violate working-time rules", violating.len(), days.len());
+        }
+
+        Ok(())
+    }
+}
+
+/// Checks every day with sessions against the configured working-time rules,
+/// with days as they fall in `timezone`
+pub fn check_days(data: &MetronData, timezone: Tz) -> Vec<DayCompliance> {
+    let rules = &data.settings.compliance;
+
+    // Worked intervals per day, keyed by the local day the session started
+    let mut by_day: BTreeMap<NaiveDate, Vec<Interval>> = BTreeMap::new();
+    for session in &data.sessions {
+        let end = session.end.unwrap_or(session.start + Duration::minutes(session.duration as i64));
+        let date = session.start.with_timezone(&timezone).date_naive();
+        by_day.entry(date).or_default().push((session.start, end, session.duration));
+    }
+
+    let mut result = Vec::new();
+    let mut previous_end: Option<(NaiveDate, DateTime<Utc>)> = None;
+
+    for (date, mut intervals) in by_day {
+        intervals.sort_by_key(|(start, _, _)| *start);
+
+        let worked_minutes: u32 = intervals.iter().map(|(_, _, duration)| duration).sum();
+        let break_minutes = break_minutes(&intervals, rules);
+        let first_start = intervals[0].0;
+        let last_end = intervals.iter().map(|(_, end, _)| *end).max().unwrap();
+
+        let mut violations = Vec::new();
+
+        if worked_minutes > rules.max_daily_minutes {
+            violations.push(format!("worked {}, more than the daily maximum of {}",
+                format_duration(worked_minutes), format_duration(rules.max_daily_minutes)));
+        }
+
+        // Only the strictest applicable break rule is reported
+        if let Some(rule) = rules.breaks.iter()
+            .filter(|b| worked_minutes > b.after_minutes)
+            .max_by_key(|b| b.break_minutes)
+        {
+            if break_minutes < rule.break_minutes {
+                violations.push(format!("{} break required after {} of work, only {} taken",
+                    format_duration(rule.break_minutes), format_duration(rule.after_minutes), format_duration(break_minutes)));
+            }
+        }
+
+        if let Some((previous_date, end)) = previous_end {
+            let rest = (first_start - end).num_minutes();
+            if previous_date.succ_opt() == Some(date) && rest < rules.min_rest_minutes as i64 {
+                violations.push(format!("only {} rest since {}, {} required",
+                    format_duration(rest.max(0) as u32), end.with_timezone(&timezone).format("%a %H:%M"), format_duration(rules.min_rest_minutes)));
+            }
+        }
+
+        previous_end = Some((date, last_end));
+        result.push(DayCompliance { date, worked_minutes, break_minutes, violations });
+    }
+
+    result
+}
+
+/// Sum of the gaps between sessions that are long enough to count as a break
+fn break_minutes(intervals: &[Interval], rules: &ComplianceRules) -> u32 {
+    let mut total = 0;
+    let mut covered_until = intervals[0].1;
+
+    for (start, end, _) in &intervals[1..] {
+        let gap = (*start - covered_until).num_minutes();
+        if gap >= rules.min_break_segment_minutes as i64 {
+            total += gap as u32;
+        }
+        covered_until = covered_until.max(*end);
+    }
+
+    total
+}
+
+fn day_start(date: NaiveDate) -> DateTime<Utc> {
+    date.and_hms_opt(0, 0, 0).unwrap().and_utc()
+}
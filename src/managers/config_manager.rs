@@ -0,0 +1,145 @@
+use crate::models::{BreakRule, ComplianceRules, QuotaPolicy, Result, Rounding};
+use crate::storage::Storage;
+use crate::utils::format_duration;
+
+pub struct ConfigManager<'a> {
+    storage: &'a mut Storage,
+}
+
+impl<'a> ConfigManager<'a> {
+    pub fn new(storage: &'a mut Storage) -> Self {
+        Self { storage }
+    }
+
+    pub fn show_config(&self) -> Result<()> {
+        let data = self.storage.get_data();
+        let settings = &data.settings;
+
+        println!("Settings:");
+        match data.total_weekly_quota {
+            Some(quota) => println!("  Total weekly quota: {}", format_duration(quota)),
+            None => println!("  Total weekly quota: not set"),
+        }
+        println!("  Quota policy: {}", settings.quota_policy);
+        match settings.tag_limit {
+            Some(limit) => println!("  Tag limit: {}", limit),
+            None => println!("  Tag limit: none"),
+        }
+        println!("  Tag rule: {}", data.describe_tag_requirement(&settings.tag_requirement));
+        for category in data.categories.iter().filter(|c| c.tag_requirement.is_some()) {
+            println!("    {}: {}", data.category_path(&category.id), data.describe_tag_requirement(data.tag_requirement_for(&category.id)));
+        }
+        println!("  Timeblock: {}", settings.timeblock);
+        for category in data.categories.iter().filter(|c| c.block_minutes.is_some() || c.rounding.is_some()) {
+            println!("    {}: {}", data.category_path(&category.id), data.timeblock_for(&category.id));
+        }
+
+        let rules = &settings.compliance;
+        println!("Working-time rules:");
+        println!("  Max per day: {}", format_duration(rules.max_daily_minutes));
+        for rule in &rules.breaks {
+            println!("  Break: {} after {}", format_duration(rule.break_minutes), format_duration(rule.after_minutes));
+        }
+        println!("  Min break segment: {}", format_duration(rules.min_break_segment_minutes));
+        println!("  Min rest between days: {}", format_duration(rules.min_rest_minutes));
+        println!("  Warn on session start/end: {}", if rules.warn_on_session { "yes" } else { "no" });
+
+        Ok(())
+    }
+
+    /// Changes the working-time rules; `reset` restores the defaults before applying other changes
+    pub fn set_compliance(
+        &mut self,
+        reset: bool,
+        max_daily: Option<u32>,
+        breaks: Vec<BreakRule>,
+        break_segment: Option<u32>,
+        rest: Option<u32>,
+        warn: Option<bool>,
+    ) -> Result<()> {
+        let data = self.storage.get_data_mut();
+        let rules = &mut data.settings.compliance;
+
+        if reset {
+            *rules = ComplianceRules::default();
+        }
+        if let Some(max_daily) = max_daily {
+            rules.max_daily_minutes = max_daily;
+        }
+        if !breaks.is_empty() {
+            rules.breaks = breaks;
+        }
+        if let Some(break_segment) = break_segment {
+            rules.min_break_segment_minutes = break_segment;
+        }
+        if let Some(rest) = rest {
+            rules.min_rest_minutes = rest;
+        }
+        if let Some(warn) = warn {
+            rules.warn_on_session = warn;
+        }
+
+        self.storage.save()?;
+
+        println!("✓ Updated working-time rules");
+        Ok(())
+    }
+
+    pub fn set_timeblock(&mut self, block_minutes: Option<u32>, rounding: Option<Rounding>) -> Result<()> {
+        let data = self.storage.get_data_mut();
+        let timeblock = &mut data.settings.timeblock;
+
+        if let Some(block_minutes) = block_minutes {
+            timeblock.block_minutes = block_minutes;
+        }
+        if let Some(rounding) = rounding {
+            timeblock.rounding = rounding;
+        }
+        let timeblock = *timeblock;
+
+        self.storage.save()?;
+
+        println!("✓ Sessions are now tracked in {}", timeblock);
+        Ok(())
+    }
+
+    /// Sets the maximum number of tags, or removes the limit if `limit` is `None`
+    pub fn set_tag_limit(&mut self, limit: Option<u32>) -> Result<()> {
+        let data = self.storage.get_data_mut();
+        data.settings.tag_limit = limit;
+        let tag_count = data.tags.len();
+        self.storage.save()?;
+
+        match limit {
+            Some(limit) => {
+                println!("✓ Set tag limit to {}", limit);
+                if tag_count > limit as usize {
+                    println!("⚠ {} tags already exist; no new tags can be created until some are removed", tag_count);
+                }
+            }
+            None => println!("✓ Removed the tag limit"),
+        }
+        Ok(())
+    }
+
+    /// Sets which tags every session needs, unless a category overrides it
+    pub fn set_tag_requirement(&mut self, group: Option<String>, off: bool) -> Result<()> {
+        let data = self.storage.get_data_mut();
+        data.settings.tag_requirement = data.tag_requirement_from(group.as_deref(), off)?;
+        let rule = data.describe_tag_requirement(&data.settings.tag_requirement);
+        self.storage.save()?;
+
+        println!("✓ Sessions now need {}", rule);
+        println!("  Run 'metron doctor' to find existing sessions that don't");
+        Ok(())
+    }
+
+    pub fn set_quota_policy(&mut self, policy: QuotaPolicy) -> Result<()> {
+        let data = self.storage.get_data_mut();
+        data.settings.quota_policy = policy;
+        self.storage.save()?;
+
+        println!("✓ Set quota policy to '{}'", policy);
+        Ok(())
+    }
+}
@@ -0,0 +1,305 @@
+use crate::models::{quota_overruns, Category, MetronData, Result, Session, Tag};
+use crate::storage::Storage;
+use crate::utils::format_duration;
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// One inconsistency found in the data, and whether `--fix` repaired it
+struct Problem {
+    message: String,
+    fixed: bool,
+}
+
+impl Problem {
+    fn new(message: String) -> Self {
+        Self { message, fixed: false }
+    }
+
+    fn fixed(message: String, fixed: bool) -> Self {
+        Self { message, fixed }
+    }
+}
+
+pub struct DoctorManager<'a> {
+    storage: &'a mut Storage,
+}
+
+impl<'a> DoctorManager<'a> {
+    pub fn new(storage: &'a mut Storage) -> Self {
+        Self { storage }
+    }
+
+    /// Reports stored data that is inconsistent or breaks the current rules;
+    /// with `fix`, repairs what can be repaired without changing tracked time
+    pub fn run(&mut self, fix: bool) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        println!("🩺 Checking {} sessions, {} categories and {} tags",
+            data.sessions.len(), data.categories.len(), data.tags.len());
+
+        let checks = [
+            ("Category references", category_problems(data, fix)),
+            ("Tag references", tag_reference_problems(data, fix)),
+            ("Session times", time_problems(data, fix)),
+            ("Timeblocks", timeblock_problems(data)),
+            ("Quotas", quota_problems(data)),
+            ("Tag rules", tag_rule_problems(data)),
+            ("Rates", rate_problems(data)),
+        ];
+
+        let mut problem_count = 0;
+        let mut fixed_count = 0;
+        for (name, problems) in &checks {
+            if problems.is_empty() {
+                println!("✓ {}", name);
+                continue;
+            }
+            println!("✗ {} ({})", name, problems.len());
+            for problem in problems {
+                let fixed = if problem.fixed { " → fixed" } else { "" };
+                println!("  {}{}", problem.message, fixed);
+            }
+            problem_count += problems.len();
+            fixed_count += problems.iter().filter(|p| p.fixed).count();
+        }
+
+        if fixed_count > 0 {
+            self.storage.save()?;
+        }
+
+        if problem_count == 0 {
+            println!("\nNo problems found.");
+        } else if fix {
+            println!("\n{} problems found, {} fixed.", problem_count, fixed_count);
+        } else {
+            println!("\n{} problems found. Run 'metron doctor --fix' to repair the safe ones.", problem_count);
+        }
+        Ok(())
+    }
+}
+
+/// IDs written by metron are UUIDs; anything else is a name kept from an older data file
+fn is_legacy_name(id: &str) -> bool {
+    Uuid::parse_str(id).is_err()
+}
+
+/// Sessions pointing at missing categories and subcategories whose parent is gone.
+/// Names kept from older files are recreated as archived categories.
+fn category_problems(data: &mut MetronData, fix: bool) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    let mut missing: BTreeMap<String, usize> = BTreeMap::new();
+    for session in data.sessions.iter().filter(|s| data.category(&s.category_id).is_none()) {
+        *missing.entry(session.category_id.clone()).or_default() += 1;
+    }
+    for (id, count) in missing {
+        let message = format!("{} sessions use missing category '{}'", count, id);
+        let repairable = is_legacy_name(&id) && data.child_named(None, &id).is_none();
+        if !(fix && repairable) {
+            problems.push(Problem::new(message));
+            continue;
+        }
+
+        let category = Category {
+            id: Uuid::new_v4().to_string(),
+            name: id.clone(),
+            category_weekly_quota: 0,
+            category_weekly_target: None,
+            block_minutes: None,
+            rounding: None,
+            archived: true,
+            parent_id: None,
+            tag_requirement: None,
+            description: None,
+            color: None,
+            rate: None,
+        };
+        for session in data.sessions.iter_mut().filter(|s| s.category_id == id) {
+            session.category_id = category.id.clone();
+        }
+        data.categories.push(category);
+        problems.push(Problem::fixed(format!("{} (recreated as archived)", message), true));
+    }
+
+    // Subcategories of a deleted parent move to the top level unless the name is taken
+    let orphans: Vec<Category> = data.categories.iter()
+        .filter(|c| c.parent_id.as_ref().is_some_and(|p| data.category(p).is_none()))
+        .cloned()
+        .collect();
+    for orphan in orphans {
+        let message = format!("Category '{}' has a missing parent", orphan.name);
+        let repairable = data.child_named(None, &orphan.name).is_none();
+        if fix && repairable {
+            if let Some(c) = data.categories.iter_mut().find(|c| c.id == orphan.id) {
+                c.parent_id = None;
+            }
+        }
+        problems.push(Problem::fixed(message, fix && repairable));
+    }
+
+    problems
+}
+
+/// Missing tags on sessions, tags listed twice and tags in deleted groups.
+/// Tag names kept from older files are recreated.
+fn tag_reference_problems(data: &mut MetronData, fix: bool) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    let mut missing: BTreeMap<String, usize> = BTreeMap::new();
+    for session in &data.sessions {
+        for id in session.tag_ids.iter().filter(|id| !data.tags.iter().any(|t| &t.id == *id)) {
+            *missing.entry(id.clone()).or_default() += 1;
+        }
+    }
+    for (id, count) in missing {
+        let message = format!("{} sessions use missing tag '{}'", count, id);
+        let repairable = is_legacy_name(&id) && data.find_tag(&id).is_none();
+        if !(fix && repairable) {
+            problems.push(Problem::new(message));
+            continue;
+        }
+
+        let tag = Tag {
+            id: Uuid::new_v4().to_string(),
+            name: id.clone(),
+            group_id: None,
+            description: None,
+            color: None,
+            rate: None,
+        };
+        for tag_id in data.sessions.iter_mut().flat_map(|s| s.tag_ids.iter_mut()).filter(|t| **t == id) {
+            *tag_id = tag.id.clone();
+        }
+        data.tags.push(tag);
+        problems.push(Problem::fixed(format!("{} (recreated)", message), true));
+    }
+
+    for session in data.sessions.iter_mut() {
+        let mut tag_ids: Vec<String> = Vec::with_capacity(session.tag_ids.len());
+        for id in &session.tag_ids {
+            if !tag_ids.contains(id) {
+                tag_ids.push(id.clone());
+            }
+        }
+        if tag_ids.len() != session.tag_ids.len() {
+            problems.push(Problem::fixed(format!("{}: same tag listed twice", short(session)), fix));
+            if fix {
+                session.tag_ids = tag_ids;
+            }
+        }
+    }
+
+    let group_ids: Vec<String> = data.tag_groups.iter().map(|g| g.id.clone()).collect();
+    for tag in data.tags.iter_mut().filter(|t| t.group_id.as_ref().is_some_and(|g| !group_ids.contains(g))) {
+        problems.push(Problem::fixed(format!("Tag '{}' is in a missing group", tag.name), fix));
+        if fix {
+            tag.group_id = None;
+        }
+    }
+
+    problems
+}
+
+/// Sessions whose end doesn't match start + duration. The duration is what all
+/// reports count, so the end time is moved to match it.
+fn time_problems(data: &mut MetronData, fix: bool) -> Vec<Problem> {
+    let mut problems = Vec::new();
+
+    let timeblocks: Vec<u32> = data.sessions.iter()
+        .map(|s| data.timeblock_for(&s.category_id).block_minutes)
+        .collect();
+    for (session, block_minutes) in data.sessions.iter_mut().zip(timeblocks) {
+        let Some(end) = session.end else { continue };
+        let elapsed = (end - session.start).num_minutes();
+
+        let message = if elapsed < 0 {
+            format!("{}: ends before it starts", short(session))
+        } else if (elapsed as u32).abs_diff(session.duration) >= block_minutes {
+            // Sessions ended early are rounded to the timeblock, so they may differ by less than a block
+            format!("{}: duration is {} but it ran {}",
+                short(session), format_duration(session.duration), format_duration(elapsed as u32))
+        } else {
+            continue;
+        };
+
+        if fix {
+            session.end = Some(session.start + chrono::Duration::minutes(session.duration as i64));
+        }
+        problems.push(Problem::fixed(message, fix));
+    }
+
+    problems
+}
+
+/// Durations that don't fit the category's timeblock, e.g. after hand edits
+fn timeblock_problems(data: &MetronData) -> Vec<Problem> {
+    data.sessions.iter()
+        .filter_map(|session| {
+            let timeblock = data.timeblock_for(&session.category_id);
+            (!timeblock.is_valid(session.duration)).then(|| Problem::new(format!(
+                "{}: {} is not a multiple of {} minutes",
+                short(session), format_duration(session.duration), timeblock.block_minutes,
+            )))
+        })
+        .collect()
+}
+
+/// Quotas smaller than what their subcategories or the categories take up
+fn quota_problems(data: &MetronData) -> Vec<Problem> {
+    quota_overruns(&data.categories, data.total_weekly_quota).into_iter()
+        .map(|overrun| {
+            let owner = match &overrun.category_id {
+                Some(id) => format!("Category '{}'", data.category_path(id)),
+                None => "Total weekly quota".to_string(),
+            };
+            let hint = match overrun.category_id {
+                Some(_) => "lower the subcategory quotas",
+                None => "see 'metron set-quota --scale'",
+            };
+            Problem::new(format!("{} is {} but {} is assigned below it ({})",
+                owner, format_duration(overrun.quota), format_duration(overrun.committed), hint))
+        })
+        .collect()
+}
+
+/// Sessions missing a required tag or using several tags of a single-choice group
+fn tag_rule_problems(data: &MetronData) -> Vec<Problem> {
+    data.sessions.iter()
+        .filter_map(|session| {
+            let result = data.check_tag_requirement(&session.category_id, &session.tag_ids)
+                .and_then(|_| data.check_tag_groups(&session.tag_ids));
+            result.err().map(|e| Problem::new(format!("{}: {}", describe(data, session), e)))
+        })
+        .collect()
+}
+
+/// Sessions whose tags carry different rates, which are billed at the highest of them
+fn rate_problems(data: &MetronData) -> Vec<Problem> {
+    data.sessions.iter()
+        .filter(|session| session.rate.is_none())
+        .filter_map(|session| {
+            let tags = data.rated_tags(session);
+            let first = tags.first()?.rate.as_ref();
+            if tags.iter().all(|t| t.rate.as_ref() == first) {
+                return None;
+            }
+            let rates: Vec<String> = tags.iter()
+                .map(|t| format!("{} {}", t.name, t.rate.as_ref().map(|r| r.to_string()).unwrap_or_default()))
+                .collect();
+            Some(Problem::new(format!("{}: tags have different rates ({}), billed at the highest; set one with 'metron session edit --rate'",
+                describe(data, session), rates.join(", "))))
+        })
+        .collect()
+}
+
+fn describe(data: &MetronData, session: &Session) -> String {
+    format!("{} ({})", short(session), data.category_path(&session.category_id))
+}
+
+/// Short ID, date and title of a session
+fn short(session: &Session) -> String {
+    format!("{} {} '{}'",
+        &session.id[..8.min(session.id.len())],
+        session.start.format("%Y-%m-%d"),
+        session.title)
+}
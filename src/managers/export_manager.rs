@@ -0,0 +1,119 @@
+use crate::formats::csv::{self, CsvOptions};
+use crate::formats::{ics, ledger, org, timewarrior};
+use crate::models::{MetronError, Result, Session};
+use crate::storage::Storage;
+use crate::utils::DateRange;
+use chrono::Utc;
+use chrono_tz::Tz;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+pub struct ExportManager<'a> {
+    storage: &'a Storage,
+}
+
+impl<'a> ExportManager<'a> {
+    pub fn new(storage: &'a Storage) -> Self {
+        Self { storage }
+    }
+
+    pub fn export_csv(&self, range: &DateRange, options: &CsvOptions, output: Option<&Path>) -> Result<()> {
+        let data = self.storage.get_data();
+        let sessions = self.sessions_in(range);
+        let what = format!("{} sessions", sessions.len());
+        write_output(output, &what, |writer| csv::write(writer, data, &sessions, options))
+    }
+
+    pub fn export_ics(&self, range: &DateRange, output: Option<&Path>) -> Result<()> {
+        let data = self.storage.get_data();
+        let sessions = self.sessions_in(range);
+        let what = format!("{} sessions", sessions.len());
+        write_output(output, &what, |writer| ics::write(writer, data, &sessions))
+    }
+
+    /// Writes ledger/hledger timeclock entries with times in `timezone`
+    pub fn export_timeclock(&self, range: &DateRange, timezone: Tz, output: Option<&Path>) -> Result<()> {
+        let data = self.storage.get_data();
+        let sessions = self.sessions_in(range);
+        let what = format!("{} sessions", sessions.len());
+        write_output(output, &what, |writer| ledger::write(writer, data, &sessions, timezone))
+    }
+
+    /// Writes org-mode headlines per category with CLOCK entries in `timezone`
+    pub fn export_org(&self, range: &DateRange, timezone: Tz, output: Option<&Path>) -> Result<()> {
+        let data = self.storage.get_data();
+        let sessions = self.sessions_in(range);
+        let what = format!("{} sessions", sessions.len());
+        write_output(output, &what, |writer| org::write(writer, data, &sessions, timezone))
+    }
+
+    /// Writes Timewarrior intervals to stdout, or into monthly `.data` files in `output`
+    pub fn export_timewarrior(&self, range: &DateRange, output: Option<&Path>) -> Result<()> {
+        let data = self.storage.get_data();
+        let sessions = self.sessions_in(range);
+        let Some(dir) = output else {
+            return write_output(None, "", |writer| timewarrior::write(writer, data, &sessions));
+        };
+
+        let files = timewarrior::monthly_files(&sessions);
+        let existing: Vec<&String> = files.keys().filter(|name| dir.join(name).exists()).collect();
+        if !existing.is_empty() {
+            println!("These files in {} will be replaced:", dir.display());
+            for name in &existing {
+                println!("  {}", name);
+            }
+            println!("Continue? (y/N)");
+
+            let mut input = String::new();
+            std::io::stdin().read_line(&mut input).unwrap();
+
+            if !input.trim().to_lowercase().starts_with('y') {
+                println!("Export cancelled.");
+                return Ok(());
+            }
+        }
+
+        std::fs::create_dir_all(dir).map_err(|e| MetronError::ExportError(e.to_string()))?;
+        for (name, sessions) in &files {
+            let file = File::create(dir.join(name)).map_err(|e| MetronError::ExportError(e.to_string()))?;
+            let mut writer = BufWriter::new(file);
+            timewarrior::write(&mut writer, data, sessions).map_err(MetronError::ExportError)?;
+            writer.flush().map_err(|e| MetronError::ExportError(e.to_string()))?;
+        }
+        println!("✓ Exported {} sessions to {} ({} files)", sessions.len(), dir.display(), files.len());
+        Ok(())
+    }
+
+    /// Sessions in the range, oldest first
+    fn sessions_in(&self, range: &DateRange) -> Vec<&Session> {
+        let now = Utc::now();
+        let mut sessions: Vec<&Session> = self.storage.get_data().sessions.iter()
+            .filter(|s| range.contains(s.start, now))
+            .collect();
+        sessions.sort_by_key(|s| s.start);
+        sessions
+    }
+}
+
+/// Writes to the given file, or to stdout if there is none. The confirmation
+/// naming `what` was written is only printed for files so stdout stays clean for piping.
+pub(crate) fn write_output<F>(output: Option<&Path>, what: &str, write: F) -> Result<()>
+where
+    F: FnOnce(&mut dyn Write) -> std::result::Result<(), String>,
+{
+    match output {
+        Some(path) => {
+            let file = File::create(path).map_err(|e| MetronError::ExportError(e.to_string()))?;
+            let mut writer = BufWriter::new(file);
+            write(&mut writer).map_err(MetronError::ExportError)?;
+            writer.flush().map_err(|e| MetronError::ExportError(e.to_string()))?;
+            println!("✓ Exported {} to {}", what, path.display());
+        }
+        None => {
+            let mut stdout = std::io::stdout().lock();
+            write(&mut stdout).map_err(MetronError::ExportError)?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,234 @@
+use crate::formats::{ImportBatch, ImportedSession, RowError};
+use crate::models::{names_match, Category, MetronData, MetronError, Result, Rounding, Session, Tag, Timeblock};
+use crate::storage::Storage;
+use crate::utils::format_duration;
+use uuid::Uuid;
+
+/// Sessions shown before a dry run ends
+const PREVIEW_ROWS: usize = 10;
+
+/// How to handle what an imported file contains, shared by all formats
+pub struct ImportOptions {
+    /// Create categories and tags that don't exist yet instead of rejecting the rows
+    pub create_missing: bool,
+    /// Import the valid rows even if others are invalid
+    pub skip_invalid: bool,
+    /// Only show what would be imported
+    pub dry_run: bool,
+    /// Round durations to each category's block size instead of rejecting those that don't fit
+    pub rounding: Option<Rounding>,
+}
+
+pub struct ImportManager<'a> {
+    storage: &'a mut Storage,
+}
+
+impl<'a> ImportManager<'a> {
+    pub fn new(storage: &'a mut Storage) -> Self {
+        Self { storage }
+    }
+
+    /// Adds the sessions read from `source` to the data file. All rows are checked
+    /// first; by default nothing is imported if any of them is invalid.
+    pub fn import(&mut self, source: &str, batch: ImportBatch, options: &ImportOptions) -> Result<()> {
+        let data = self.storage.get_data_mut();
+        let mut working = data.clone();
+
+        println!("📥 Read {} sessions from {}", batch.sessions.len(), source);
+        if batch.ignored > 0 {
+            println!("  Ignored {} entries without tracked time", batch.ignored);
+        }
+
+        let (created_categories, created_tags, skipped_tags) = if options.create_missing {
+            create_missing(&mut working, &batch.sessions)
+        } else {
+            (Vec::new(), Vec::new(), Vec::new())
+        };
+
+        let mut errors = batch.errors;
+        let mut imported = Vec::new();
+        let mut duplicates = 0;
+        for record in &batch.sessions {
+            match resolve(&working, record, options.rounding) {
+                Ok(Some(session)) => {
+                    working.sessions.push(session.clone());
+                    imported.push(session);
+                }
+                Ok(None) => duplicates += 1,
+                Err(message) => errors.push(RowError { position: record.position.clone(), message }),
+            }
+        }
+        errors.sort_by(|a, b| a.position.cmp(&b.position));
+
+        let verb = if options.dry_run { "Would create" } else { "Creating" };
+        if !created_categories.is_empty() {
+            println!("  {} categories: {}", verb, created_categories.join(", "));
+        }
+        if !created_tags.is_empty() {
+            println!("  {} tags: {}", verb, created_tags.join(", "));
+        }
+        if let (false, Some(limit)) = (skipped_tags.is_empty(), working.settings.tag_limit) {
+            println!("⚠ Not creating tags {}: {} (see 'metron config tag-limit')",
+                skipped_tags.join(", "), MetronError::TagLimitExceeded(limit));
+        }
+        for error in &errors {
+            println!("✗ {}: {}", error.position, error.message);
+        }
+
+        if !errors.is_empty() && !options.skip_invalid && !options.dry_run {
+            println!("Nothing imported. Fix these rows, or use --skip-invalid to import the rest");
+            if !options.create_missing {
+                println!("  Use --create-missing to create unknown categories and tags");
+            }
+            return Err(MetronError::ImportError(format!("{} invalid rows", errors.len())));
+        }
+
+        if options.dry_run {
+            println!("\n{:<20} {:<25} {:<20} {:<10} Tags", "Start", "Title", "Category", "Duration");
+            for session in imported.iter().take(PREVIEW_ROWS) {
+                println!("{:<20} {:<25} {:<20} {:<10} {}",
+                    session.start.format("%Y-%m-%d %H:%M"),
+                    session.title,
+                    working.category_path(&session.category_id),
+                    format_duration(session.duration),
+                    session.tag_ids.iter().map(|id| working.tag_name(id)).collect::<Vec<_>>().join(", "));
+            }
+            if imported.len() > PREVIEW_ROWS {
+                println!("... and {} more", imported.len() - PREVIEW_ROWS);
+            }
+            println!("\nDry run: {} sessions would be imported, {} duplicates skipped, {} invalid rows. Nothing was saved.",
+                imported.len(), duplicates, errors.len());
+            return Ok(());
+        }
+
+        *data = working;
+        self.storage.save()?;
+
+        let total: u32 = imported.iter().map(|s| s.duration).sum();
+        println!("✓ Imported {} sessions ({})", imported.len(), format_duration(total));
+        if duplicates > 0 {
+            println!("  Skipped {} sessions that were already imported", duplicates);
+        }
+        if !errors.is_empty() {
+            println!("  Skipped {} invalid rows", errors.len());
+        }
+        println!("  Run 'metron doctor' to check them against quotas");
+        Ok(())
+    }
+}
+
+/// Creates the categories (with any missing parents) and tags named in the
+/// file, returning what was created. Tags beyond the tag limit are returned
+/// last instead of created, so their rows fail as unknown tags.
+fn create_missing(data: &mut MetronData, sessions: &[ImportedSession]) -> (Vec<String>, Vec<String>, Vec<String>) {
+    let mut categories = Vec::new();
+    let mut tags = Vec::new();
+    let mut skipped_tags: Vec<String> = Vec::new();
+
+    for session in sessions {
+        let mut parent: Option<String> = None;
+        for segment in session.category.split('/').map(str::trim).filter(|s| !s.is_empty()) {
+            let existing = data.child_named(parent.as_deref(), segment).map(|c| c.id.clone());
+            let id = existing.unwrap_or_else(|| {
+                let category = Category {
+                    id: Uuid::new_v4().to_string(),
+                    name: segment.to_string(),
+                    category_weekly_quota: 0,
+                    category_weekly_target: None,
+                    block_minutes: None,
+                    rounding: None,
+                    archived: false,
+                    parent_id: parent.clone(),
+                    tag_requirement: None,
+                    description: None,
+                    color: None,
+                    rate: None,
+                };
+                let id = category.id.clone();
+                data.categories.push(category);
+                categories.push(data.category_path(&id));
+                id
+            });
+            parent = Some(id);
+        }
+
+        for name in &session.tags {
+            if data.find_tag(name).is_some() || skipped_tags.iter().any(|t| names_match(t, name)) {
+                continue;
+            }
+            if data.settings.tag_limit.is_some_and(|limit| data.tags.len() >= limit as usize) {
+                skipped_tags.push(name.clone());
+                continue;
+            }
+            data.tags.push(Tag {
+                id: Uuid::new_v4().to_string(),
+                name: name.clone(),
+                group_id: None,
+                description: None,
+                color: None,
+                rate: None,
+            });
+            tags.push(name.clone());
+        }
+    }
+
+    (categories, tags, skipped_tags)
+}
+
+/// Turns a record into a session, or `None` if it was imported before
+fn resolve(data: &MetronData, record: &ImportedSession, rounding: Option<Rounding>) -> std::result::Result<Option<Session>, String> {
+    // Keep IDs from metron's own exports so importing the same file twice is harmless
+    let id = match record.id.as_deref().filter(|id| Uuid::parse_str(id).is_ok()) {
+        Some(id) if data.sessions.iter().any(|s| s.id == id) => return Ok(None),
+        Some(id) => id.to_string(),
+        None => Uuid::new_v4().to_string(),
+    };
+    // Other trackers' records are recognized by their import key
+    if record.import_key.is_some() && data.sessions.iter().any(|s| s.import_key == record.import_key) {
+        return Ok(None);
+    }
+
+    let category = data.find_category(&record.category)
+        .ok_or_else(|| format!("unknown category '{}'", record.category))?;
+    if category.archived {
+        return Err(format!("{}: {}", MetronError::CategoryArchived, data.category_path(&category.id)));
+    }
+
+    let mut tag_ids: Vec<String> = Vec::new();
+    for name in &record.tags {
+        let tag = data.find_tag(name).ok_or_else(|| format!("unknown tag '{}'", name))?;
+        if !tag_ids.contains(&tag.id) {
+            tag_ids.push(tag.id.clone());
+        }
+    }
+    // The same tag rules as 'metron session add'
+    data.check_tag_groups(&tag_ids)
+        .and_then(|_| data.check_tag_requirement(&category.id, &tag_ids))
+        .map_err(|e| e.to_string())?;
+
+    let timeblock = data.timeblock_for(&category.id);
+    let duration = match rounding {
+        Some(rounding) => Timeblock { rounding, ..timeblock }.round(record.duration),
+        None => record.duration,
+    };
+    if duration == 0 {
+        return Err(format!("{} rounds down to nothing", format_duration(record.duration)));
+    }
+    if !timeblock.is_valid(duration) {
+        return Err(format!("{} doesn't fit the {} timeblock of '{}' (see --rounding)",
+            format_duration(duration), timeblock, data.category_path(&category.id)));
+    }
+
+    Ok(Some(Session {
+        id,
+        title: record.title.clone(),
+        category_id: category.id.clone(),
+        tag_ids,
+        start: record.start,
+        end: Some(record.start + chrono::Duration::minutes(duration as i64)),
+        duration,
+        import_key: record.import_key.clone(),
+        rate: None,
+        non_billable: false,
+    }))
+}
@@ -0,0 +1,161 @@
+use super::export_manager::write_output;
+use crate::models::{MetronError, Result, Session};
+use crate::report::invoice::{self, Invoice, InvoiceFormat, LineItem};
+use crate::storage::Storage;
+use crate::utils::{format_duration, format_money, local_now, DateRange};
+use chrono::Utc;
+use chrono_tz::Tz;
+use std::collections::BTreeMap;
+use std::path::Path;
+
+pub struct InvoiceOptions {
+    pub per_session: bool, // one line per session instead of per category and rate
+    pub tax_percent: Option<f64>,
+    pub timezone: Tz,
+}
+
+pub struct InvoiceManager<'a> {
+    storage: &'a Storage,
+}
+
+impl<'a> InvoiceManager<'a> {
+    pub fn new(storage: &'a Storage) -> Self {
+        Self { storage }
+    }
+
+    pub fn generate(
+        &self,
+        client: &str,
+        range: &DateRange,
+        options: &InvoiceOptions,
+        format: InvoiceFormat,
+        output: Option<&Path>,
+    ) -> Result<()> {
+        let invoice = self.build(client, range, options)?;
+        let what = format!("invoice for {} ({})", invoice.client, format_money(invoice.total, invoice.currency.as_deref()));
+        write_output(output, &what, |writer| match format {
+            InvoiceFormat::Markdown => writer.write_all(invoice::markdown(&invoice).as_bytes()).map_err(|e| e.to_string()),
+            InvoiceFormat::Csv => invoice::csv(writer, &invoice),
+        })
+    }
+
+    /// Collects the billable sessions of a category and its subcategories in
+    /// `range`, by their dates in the invoice's time zone, into line items.
+    /// Time that isn't billed is reported on stderr so it stays out of the
+    /// invoice.
+    pub fn build(&self, client: &str, range: &DateRange, options: &InvoiceOptions) -> Result<Invoice> {
+        let data = self.storage.get_data();
+        let now = Utc::now();
+
+        let client = data.find_category(client).ok_or(MetronError::CategoryNotFound)?;
+        let client_path = data.category_path(&client.id);
+        let ids = data.subtree_ids(&client.id);
+
+        let mut sessions: Vec<&Session> = data.sessions.iter()
+            .filter(|s| range.contains_local(s.start, now, options.timezone) && ids.contains(&s.category_id))
+            .collect();
+        sessions.sort_by_key(|s| s.start);
+
+        let mut billed = Vec::new();
+        let (mut non_billable, mut unrated) = (Vec::new(), Vec::new());
+        for session in sessions {
+            if session.non_billable {
+                non_billable.push(session);
+                continue;
+            }
+            match data.rate_for(session) {
+                Some(rate) => billed.push((session, rate)),
+                None => unrated.push(session),
+            }
+        }
+        let minutes = |sessions: &[&Session]| sessions.iter().map(|s| s.duration).sum::<u32>();
+        if !non_billable.is_empty() {
+            eprintln!("Not billed: {} of non-billable sessions", format_duration(minutes(&non_billable)));
+        }
+        if !unrated.is_empty() {
+            eprintln!("⚠ Not billed: {} of sessions without a rate (see metron category rate)",
+                format_duration(minutes(&unrated)));
+        }
+
+        // Rates without a currency count as one of their own, so they aren't billed as another's
+        let mut currencies: Vec<Option<&str>> = billed.iter().map(|(_, r)| r.currency.as_deref()).collect();
+        currencies.sort();
+        currencies.dedup();
+        if currencies.len() > 1 {
+            let names: Vec<&str> = currencies.iter().map(|c| c.unwrap_or("no currency")).collect();
+            return Err(MetronError::InvoiceError(format!(
+                "sessions of '{}' are billed in {}, use one currency per client", client_path, names.join(" and "))));
+        }
+        if billed.is_empty() {
+            return Err(MetronError::InvoiceError(format!("no billable sessions of '{}' in {}", client_path, range)));
+        }
+
+        let description = |category_id: &str| {
+            let path = data.category_path(category_id);
+            match path.strip_prefix(&format!("{}/", client_path)) {
+                Some(relative) => relative.to_string(),
+                None => path,
+            }
+        };
+        let too_large = || MetronError::InvoiceError(format!("the amounts of '{}' are too large to bill", client_path));
+        let amount = |rate: u64, minutes: u32| {
+            rate.checked_mul(minutes.into()).and_then(|cents| cents.checked_add(30)).map(|cents| cents / 60).ok_or_else(too_large)
+        };
+
+        let lines: Vec<LineItem> = if options.per_session {
+            billed.iter()
+                .map(|(session, rate)| {
+                    let category = description(&session.category_id);
+                    Ok(LineItem {
+                        date: Some(session.start.with_timezone(&options.timezone).date_naive()),
+                        description: if session.category_id == client.id {
+                            session.title.clone()
+                        } else {
+                            format!("{} ({})", session.title, category)
+                        },
+                        time: session.duration.into(),
+                        rate: rate.cents,
+                        amount: amount(rate.cents, session.duration)?,
+                    })
+                })
+                .collect::<Result<_>>()?
+        } else {
+            // One line per category and rate, in tree order with higher rates first
+            let order: Vec<String> = data.category_tree().into_iter().map(|(c, _)| c.id.clone()).collect();
+            let mut groups: BTreeMap<(usize, std::cmp::Reverse<u64>), (&str, u32)> = BTreeMap::new();
+            for (session, rate) in &billed {
+                let position = order.iter().position(|id| *id == session.category_id).unwrap_or(order.len());
+                let entry = groups.entry((position, std::cmp::Reverse(rate.cents))).or_insert((&session.category_id, 0));
+                entry.1 += session.duration;
+            }
+            groups.into_iter()
+                .map(|((_, rate), (category_id, minutes))| Ok(LineItem {
+                    date: None,
+                    description: description(category_id),
+                    time: minutes.into(),
+                    rate: rate.0,
+                    amount: amount(rate.0, minutes)?,
+                }))
+                .collect::<Result<_>>()?
+        };
+
+        let subtotal = lines.iter().try_fold(0u64, |sum, l| sum.checked_add(l.amount)).ok_or_else(too_large)?;
+        let tax = options.tax_percent
+            .map(|percent| (subtotal as f64 * percent / 100.0).round() as u64)
+            .unwrap_or(0);
+        let total = subtotal.checked_add(tax).ok_or_else(too_large)?;
+        let (first_day, last_day) = range.bounds(local_now(now, options.timezone));
+        Ok(Invoice {
+            client: client_path,
+            first_day,
+            last_day,
+            currency: currencies.first().copied().flatten().map(str::to_string),
+            time: lines.iter().map(|l| l.time.minutes).sum::<u32>().into(),
+            lines,
+            subtotal,
+            tax_percent: options.tax_percent,
+            tax,
+            total,
+        })
+    }
+}
@@ -2,8 +2,24 @@ pub mod category_manager;
 pub mod tag_manager;
 pub mod session_manager;
 pub mod analysis_manager;
+pub mod config_manager;
+pub mod compliance_manager;
+pub mod doctor_manager;
+pub mod quota_manager;
+pub mod import_manager;
+pub mod export_manager;
+pub mod report_manager;
+pub mod invoice_manager;
 
 pub use category_manager::CategoryManager;
 pub use tag_manager::TagManager;
-pub use session_manager::SessionManager;
+pub use session_manager::{SessionChanges, SessionManager};
 pub use analysis_manager::AnalysisManager;
+pub use config_manager::ConfigManager;
+pub use compliance_manager::ComplianceManager;
+pub use doctor_manager::DoctorManager;
+pub use quota_manager::QuotaManager;
+pub use import_manager::{ImportManager, ImportOptions};
+pub use export_manager::ExportManager;
+pub use report_manager::ReportManager;
+pub use invoice_manager::{InvoiceManager, InvoiceOptions};
@@ -0,0 +1,91 @@
+use crate::models::{check_quota_tree, MetronError, Result};
+use crate::storage::Storage;
+use crate::utils::format_duration;
+
+pub struct QuotaManager<'a> {
+    storage: &'a mut Storage,
+}
+
+impl<'a> QuotaManager<'a> {
+    pub fn new(storage: &'a mut Storage) -> Self {
+        Self { storage }
+    }
+
+    /// Sets the total weekly quota. If the categories already take up more,
+    /// this fails unless `scale` is set, in which case every active category's
+    /// quota is scaled down by the same factor after confirmation.
+    pub fn set_total_quota(&mut self, quota: u32, scale: bool) -> Result<()> {
+        let data = self.storage.get_data_mut();
+        let committed = data.committed_quota();
+
+        if committed <= quota {
+            data.total_weekly_quota = Some(quota);
+            self.storage.save()?;
+
+            println!("✓ Set total weekly quota to {}", format_duration(quota));
+            println!("  Categories use {}, {} unassigned", format_duration(committed), format_duration(quota - committed));
+            return Ok(());
+        }
+
+        if !scale {
+            println!("✗ Categories already use {} of quota, more than {}", format_duration(committed), format_duration(quota));
+            println!("  Lower category quotas first, or use --scale to shrink them proportionally");
+            return Err(MetronError::QuotaExceeded);
+        }
+
+        // Scaling every level by the same factor keeps children within their parents,
+        // since rounding down never makes a sum larger. A quota rounded down to 0
+        // would mean no quota at all, so such scales are refused.
+        let mut categories = data.categories.clone();
+        let mut changes = Vec::new();
+        for category in categories.iter_mut().filter(|c| !c.archived && c.category_weekly_quota > 0) {
+            let scaled = (category.category_weekly_quota as u64 * quota as u64 / committed as u64) as u32;
+            changes.push((category.id.clone(), category.category_weekly_quota, scaled));
+            category.category_weekly_quota = scaled;
+        }
+        let vanishing: Vec<String> = changes.iter()
+            .filter(|(_, _, new)| *new == 0)
+            .map(|(id, _, _)| data.category_path(id))
+            .collect();
+        if !vanishing.is_empty() {
+            println!("✗ Scaling to {} would remove the quotas of {}", format_duration(quota), vanishing.join(", "));
+            println!("  Clear or lower some category quotas first");
+            return Err(MetronError::QuotaExceeded);
+        }
+        check_quota_tree(&categories, Some(quota))?;
+
+        println!("Category quotas will be scaled to fit {}:", format_duration(quota));
+        for (id, old, new) in &changes {
+            println!("  {}: {} → {}", data.category_path(id), format_duration(*old), format_duration(*new));
+        }
+        println!("Continue? (y/N)");
+
+        let mut input = String::new();
+        std::io::stdin().read_line(&mut input).unwrap();
+
+        if !input.trim().to_lowercase().starts_with('y') {
+            println!("Quota change cancelled.");
+            return Ok(());
+        }
+
+        data.categories = categories;
+        data.total_weekly_quota = Some(quota);
+        self.storage.save()?;
+
+        println!("✓ Set total weekly quota to {} ({} categories scaled)", format_duration(quota), changes.len());
+        Ok(())
+    }
+
+    /// Removes the total weekly quota; category quotas stay as they are
+    pub fn clear_total_quota(&mut self) -> Result<()> {
+        let data = self.storage.get_data_mut();
+        let old = data.total_weekly_quota.take();
+        self.storage.save()?;
+
+        match old {
+            Some(old) => println!("✓ Removed total weekly quota (was {})", format_duration(old)),
+            None => println!("✓ No total weekly quota was set"),
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,239 @@
+use super::analysis_manager::overtime;
+use super::export_manager::write_output;
+use crate::models::{MetronError, Result, Session};
+use crate::report::{self, CategoryTotal, Day, Report, ReportFormat, SessionRow, TagTotal, Time, Totals};
+use crate::storage::Storage;
+use crate::utils::{local_now, week_bounds, DateRange};
+use chrono::{Duration, NaiveDate, Utc};
+use chrono_tz::Tz;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+
+/// Ranges up to this many days list every day, longer ones only days with sessions
+const MAX_LISTED_DAYS: i64 = 62;
+
+pub struct ReportManager<'a> {
+    storage: &'a Storage,
+}
+
+impl<'a> ReportManager<'a> {
+    pub fn new(storage: &'a Storage) -> Self {
+        Self { storage }
+    }
+
+    pub fn generate(
+        &self,
+        range: &DateRange,
+        category: Option<&str>,
+        timezone: Tz,
+        format: ReportFormat,
+        template: Option<&str>,
+        output: Option<&Path>,
+    ) -> Result<()> {
+        // Look the template up first so a typo doesn't wait for the report
+        let template = template.map(report::template::find).transpose().map_err(MetronError::TemplateError)?;
+        let report = self.build(range, category, timezone)?;
+        let document = match (template, format) {
+            (Some(path), _) => report::template::render(&report, &path).map_err(MetronError::TemplateError)?,
+            (None, ReportFormat::Markdown) => report::markdown::render(&report),
+            (None, ReportFormat::Html) => report::html::render(&report),
+            (None, ReportFormat::Json) => {
+                let mut json = serde_json::to_string_pretty(&report).map_err(|e| MetronError::ExportError(e.to_string()))?;
+                json.push('\n');
+                json
+            }
+        };
+        let what = format!("report of {} sessions", report.total.sessions);
+        write_output(output, &what, |writer| {
+            writer.write_all(document.as_bytes()).map_err(|e| e.to_string())
+        })
+    }
+
+    /// Collects what a report shows about the sessions in `range`, optionally
+    /// limited to a category and its subcategories. Days are those of `timezone`,
+    /// so sessions near midnight land on the day they show up on.
+    pub fn build(&self, range: &DateRange, category: Option<&str>, timezone: Tz) -> Result<Report> {
+        let data = self.storage.get_data();
+        let now = Utc::now();
+
+        let filter_root = match category {
+            Some(name) => Some(data.find_category(name).ok_or(MetronError::CategoryNotFound)?.id.clone()),
+            None => None,
+        };
+        let filter_ids = filter_root.as_ref().map(|id| data.subtree_ids(id));
+
+        let mut sessions: Vec<&Session> = data.sessions.iter()
+            .filter(|s| range.contains_local(s.start, now, timezone))
+            .filter(|s| filter_ids.as_ref().is_none_or(|ids| ids.contains(&s.category_id)))
+            .collect();
+        sessions.sort_by_key(|s| s.start);
+
+        // Roll every session up to its category and all of that category's parents,
+        // for the whole range and week by week for quotas
+        let mut by_category: HashMap<String, Vec<&Session>> = HashMap::new();
+        let mut by_week: BTreeMap<NaiveDate, HashMap<String, Vec<&Session>>> = BTreeMap::new();
+        for session in &sessions {
+            let week = by_week.entry(week_bounds(session.start).0.date_naive()).or_default();
+            for id in rollup_ids(data, session) {
+                by_category.entry(id.clone()).or_default().push(session);
+                week.entry(id).or_default().push(session);
+            }
+        }
+
+        // Categories in tree order, followed by IDs that no longer match a category
+        let mut nodes: Vec<(String, usize)> = data.category_tree().into_iter()
+            .filter(|(c, _)| by_category.contains_key(&c.id))
+            .filter(|(c, _)| filter_ids.as_ref().is_none_or(|ids| ids.contains(&c.id)))
+            .map(|(c, depth)| (c.id.clone(), depth))
+            .collect();
+        let mut dangling: Vec<String> = by_category.keys()
+            .filter(|id| data.category(id).is_none())
+            .cloned()
+            .collect();
+        dangling.sort();
+        nodes.extend(dangling.into_iter().map(|id| (id, 0)));
+        let base_depth = nodes.iter().map(|(_, depth)| *depth).min().unwrap_or(0);
+        let roots: Vec<String> = nodes.iter()
+            .filter(|(_, depth)| *depth == base_depth)
+            .map(|(id, _)| id.clone())
+            .collect();
+
+        let total_minutes: u32 = sessions.iter().map(|s| s.duration).sum();
+        let share = |minutes: u32| {
+            if total_minutes == 0 { 0.0 } else { (minutes as f64 * 1000.0 / total_minutes as f64).round() / 10.0 }
+        };
+
+        let categories: Vec<CategoryTotal> = nodes.iter()
+            .map(|(id, depth)| {
+                let category = data.category(id);
+                let minutes: u32 = by_category[id].iter().map(|s| s.duration).sum();
+                let over: u32 = by_week.values().map(|week| overtime(data, id, week)).sum();
+                CategoryTotal {
+                    name: category.map(|c| c.name.clone()).unwrap_or_else(|| id.clone()),
+                    path: data.category_path(id),
+                    depth: depth - base_depth,
+                    description: category.and_then(|c| c.description.clone()),
+                    color: data.category_color(id).map(str::to_string),
+                    sessions: by_category[id].len(),
+                    time: minutes.into(),
+                    weekly_quota: category.map(|c| c.category_weekly_quota).filter(|q| *q > 0).map(Time::from),
+                    work: (minutes - over).into(),
+                    overtime: over.into(),
+                    share: share(minutes),
+                }
+            })
+            .collect();
+        let total_overtime: u32 = categories.iter()
+            .filter(|c| c.depth == 0)
+            .map(|c| c.overtime.minutes)
+            .sum();
+
+        let mut tag_totals: HashMap<&str, (usize, u32)> = HashMap::new();
+        for session in &sessions {
+            for tag_id in &session.tag_ids {
+                let entry = tag_totals.entry(tag_id.as_str()).or_default();
+                entry.0 += 1;
+                entry.1 += session.duration;
+            }
+        }
+        let mut tags: Vec<TagTotal> = tag_totals.into_iter()
+            .map(|(id, (count, minutes))| TagTotal {
+                name: data.tag_name(id).to_string(),
+                color: data.tag_color(id).map(str::to_string),
+                sessions: count,
+                time: minutes.into(),
+                share: share(minutes),
+            })
+            .collect();
+        tags.sort_by(|a, b| b.time.minutes.cmp(&a.time.minutes).then_with(|| a.name.cmp(&b.name)));
+
+        // Days in the report's time zone, each split by top-level category
+        let local_date = |s: &Session| s.start.with_timezone(&timezone).date_naive();
+        let mut by_day: BTreeMap<NaiveDate, Vec<&Session>> = BTreeMap::new();
+        for session in &sessions {
+            by_day.entry(local_date(session)).or_default().push(session);
+        }
+        let (bound_first, bound_last) = range.bounds(local_now(now, timezone));
+        let first_day = bound_first.or_else(|| by_day.keys().next().copied());
+        let last_day = bound_last.or_else(|| by_day.keys().next_back().copied());
+        let dates: Vec<NaiveDate> = match (first_day, last_day) {
+            (Some(first), Some(last)) if (last - first).num_days() <= MAX_LISTED_DAYS => {
+                first.iter_days().take_while(|d| *d <= last).collect()
+            }
+            _ => by_day.keys().copied().collect(),
+        };
+        let days = dates.into_iter()
+            .map(|date| {
+                let sessions = by_day.get(&date).map(Vec::as_slice).unwrap_or_default();
+                let columns = roots.iter()
+                    .map(|root| {
+                        sessions.iter()
+                            .filter(|s| rollup_ids(data, s).contains(root))
+                            .map(|s| s.duration)
+                            .sum::<u32>()
+                            .into()
+                    })
+                    .collect();
+                Day {
+                    date,
+                    weekday: date.format("%a").to_string(),
+                    sessions: sessions.len(),
+                    time: sessions.iter().map(|s| s.duration).sum::<u32>().into(),
+                    columns,
+                }
+            })
+            .collect();
+
+        let session_rows = sessions.iter()
+            .map(|s| {
+                let start = s.start.with_timezone(&timezone);
+                SessionRow {
+                    id: s.id.clone(),
+                    date: start.date_naive(),
+                    start: start.format("%H:%M").to_string(),
+                    end: (start + Duration::minutes(s.duration as i64)).format("%H:%M").to_string(),
+                    title: s.title.clone(),
+                    category: data.category_path(&s.category_id),
+                    tags: s.tag_ids.iter().map(|id| data.tag_name(id).to_string()).collect(),
+                    time: s.duration.into(),
+                }
+            })
+            .collect();
+
+        let category = filter_root.map(|id| data.category_path(&id));
+        Ok(Report {
+            title: match &category {
+                Some(path) => format!("Timesheet: {}", path),
+                None => "Timesheet".to_string(),
+            },
+            period: range.to_string(),
+            category,
+            first_day,
+            last_day,
+            generated: now.with_timezone(&timezone).format("%Y-%m-%d %H:%M").to_string(),
+            timezone: timezone.to_string(),
+            columns: roots.iter().map(|id| data.category_path(id)).collect(),
+            days,
+            categories,
+            tags,
+            sessions: session_rows,
+            total: Totals {
+                sessions: sessions.len(),
+                days_worked: by_day.len(),
+                time: total_minutes.into(),
+                work: (total_minutes - total_overtime).into(),
+                overtime: total_overtime.into(),
+            },
+        })
+    }
+}
+
+/// The session's category and all its parents; just the ID if it matches no category
+fn rollup_ids(data: &crate::models::MetronData, session: &Session) -> Vec<String> {
+    let chain = data.ancestors(&session.category_id);
+    if chain.is_empty() {
+        vec![session.category_id.clone()]
+    } else {
+        chain.into_iter().map(|c| c.id.clone()).collect()
+    }
+}
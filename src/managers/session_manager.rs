@@ -1,8 +1,25 @@
-use crate::models::{Session, MetronError, Result};
+use crate::models::{MetronData, QuotaPolicy, Rate, Session, Tag, TagRequirement, MetronError, Result};
 use crate::storage::Storage;
-use chrono::Utc;
+use crate::managers::compliance_manager;
+use crate::utils::{format_duration, paint};
+use chrono::{DateTime, NaiveDate, Utc};
+use chrono_tz::Tz;
+use std::collections::{HashMap, HashSet};
 use uuid::Uuid;
 
+/// Fields to change on a logged session; `None` keeps the current value
+pub struct SessionChanges {
+    pub title: Option<String>,
+    pub category: Option<String>,
+    pub tags: Vec<String>,
+    pub clear_tags: bool,
+    pub start: Option<DateTime<Utc>>,
+    pub duration: Option<u32>,
+    pub rate: Option<Rate>,
+    pub clear_rate: bool,
+    pub billable: Option<bool>,
+}
+
 pub struct SessionManager<'a> {
     storage: &'a mut Storage,
 }
@@ -12,73 +29,333 @@ impl<'a> SessionManager<'a> {
         Self { storage }
     }
 
-    pub fn start_session(&mut self, title: String, category: String, tags: Vec<String>, duration: u32) -> Result<()> {
-        let data = self.storage.get_data_mut();
-        
-        // Validate duration is multiple of 15
-        if duration == 0 || duration % 15 != 0 {
-            return Err(MetronError::InvalidDuration);
+    pub fn start_session(&mut self, title: String, category: String, tags: Vec<String>, duration: u32, non_billable: bool) -> Result<()> {
+        let now = Utc::now();
+        let end_time = now + chrono::Duration::minutes(duration as i64);
+
+        let (session, notes) = match self.create_session(&title, &category, &tags, now, duration, non_billable) {
+            Ok(created) => created,
+            Err(e) => {
+                if let MetronError::MissingTag(_) = e {
+                    let data = self.storage.get_data();
+                    if let Some(category) = data.find_category(&category) {
+                        let suggested = suggest_tags(data, &category.id, &title);
+                        if !suggested.is_empty() {
+                            println!("Tags often used here: {}", suggested.join(", "));
+                        }
+                    }
+                }
+                return Err(e);
+            }
+        };
+        let data = self.storage.get_data();
+
+        println!("✓ Started session '{}' in category '{}' for {}",
+            title, data.category_path(&session.category_id), format_duration(duration));
+        if !session.tag_ids.is_empty() {
+            println!("  Tags: {}", data.painted_tag_names(&session).join(", "));
+        }
+        println!("  Session will end at: {}", end_time.format("%H:%M:%S"));
+        if non_billable {
+            println!("  Not billable");
+        }
+        if session.tag_ids.is_empty() {
+            let suggested = suggest_tags(data, &session.category_id, &title);
+            if !suggested.is_empty() {
+                println!("  Suggested tags: {}", suggested.join(", "));
+                println!("  Add them with: metron session edit {} --tags <TAG>", &session.id[..8]);
+            }
         }
+        for note in notes {
+            println!("{}", note);
+        }
+
+        Ok(())
+    }
 
-        // Check if category exists
-        if !data.categories.iter().any(|c| c.name == category) {
-            return Err(MetronError::CategoryNotFound);
+    pub fn add_session(&mut self, title: String, category: String, tags: Vec<String>, start: DateTime<Utc>, duration: u32, non_billable: bool) -> Result<()> {
+        if start > Utc::now() {
+            return Err(MetronError::StartInFuture);
         }
+        let (session, notes) = self.create_session(&title, &category, &tags, start, duration, non_billable)?;
+        let data = self.storage.get_data();
 
-        // Check if all tags exist
-        for tag in &tags {
-            if !data.tags.iter().any(|t| t.name == *tag) {
-                return Err(MetronError::TagNotFound);
-            }
+        println!("✓ Added session '{}' in category '{}' for {}",
+            title, data.category_path(&session.category_id), format_duration(duration));
+        if !session.tag_ids.is_empty() {
+            println!("  Tags: {}", data.painted_tag_names(&session).join(", "));
+        }
+        println!("  Logged: {} - {}",
+            start.format("%Y-%m-%d %H:%M"),
+            (start + chrono::Duration::minutes(duration as i64)).format("%H:%M")
+        );
+        if non_billable {
+            println!("  Not billable");
+        }
+        for note in notes {
+            println!("{}", note);
         }
 
-        let now = Utc::now();
-        let end_time = now + chrono::Duration::minutes(duration as i64);
+        Ok(())
+    }
+
+    /// Validates and stores a new session, returning it with notes to print after the confirmation
+    fn create_session(&mut self, title: &str, category: &str, tags: &[String], start: DateTime<Utc>, duration: u32, non_billable: bool) -> Result<(Session, Vec<String>)> {
+        let data = self.storage.get_data_mut();
         
+        // Check if category exists and is still open for new sessions
+        let category = data.find_category(category).ok_or(MetronError::CategoryNotFound)?;
+        if category.archived {
+            return Err(MetronError::CategoryArchived);
+        }
+        let category_id = category.id.clone();
+
+        // Validate duration fits the category's timeblock
+        let timeblock = data.timeblock_for(&category_id);
+        if !timeblock.is_valid(duration) {
+            return Err(MetronError::InvalidDuration(timeblock.block_minutes));
+        }
+
+        let tag_ids = Self::resolve_tags(data, tags)?;
+        data.check_tag_groups(&tag_ids)?;
+        data.check_tag_requirement(&category_id, &tag_ids)?;
+
+        let mut notes = Vec::new();
+        Self::check_category_quota(data, &category_id, start, duration, &mut notes)?;
+
         let session = Session {
             id: Uuid::new_v4().to_string(),
-            title: title.clone(),
-            category: category.clone(),
-            tags: tags.clone(),
-            start: now,
-            end: Some(end_time),
+            title: title.to_string(),
+            category_id,
+            tag_ids,
+            start,
+            end: Some(start + chrono::Duration::minutes(duration as i64)),
             duration,
+            import_key: None,
+            rate: None,
+            non_billable,
         };
 
-        data.sessions.push(session);
+        let date = session.start.date_naive();
+        data.sessions.push(session.clone());
+        Self::check_compliance(data, date, &mut notes);
         self.storage.save()?;
 
-        println!("✓ Started session '{}' in category '{}' for {} minutes", title, category, duration);
-        if !tags.is_empty() {
-            println!("  Tags: {}", tags.join(", "));
+        Ok((session, notes))
+    }
+
+    /// Changes a logged session, checking the new values like a new session
+    pub fn edit_session(&mut self, id: String, changes: SessionChanges) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let index = data.sessions.iter()
+            .position(|s| s.id == id || s.id.starts_with(&id))
+            .ok_or(MetronError::SessionNotFound)?;
+
+        // Take the session out so it doesn't count against its own quota while checking
+        let old = data.sessions.remove(index);
+        let mut notes = Vec::new();
+        match Self::apply_edit(data, &old, changes, &mut notes) {
+            Ok(session) => data.sessions.insert(index, session),
+            Err(e) => {
+                data.sessions.insert(index, old);
+                return Err(e);
+            }
+        }
+
+        let session = &data.sessions[index];
+        let date = session.start.date_naive();
+        Self::check_compliance(data, date, &mut notes);
+        self.storage.save()?;
+
+        let data = self.storage.get_data();
+        let session = &data.sessions[index];
+        println!("✓ Updated session '{}' in category '{}' for {}",
+            session.title, data.category_path(&session.category_id), format_duration(session.duration));
+        if !session.tag_ids.is_empty() {
+            println!("  Tags: {}", data.painted_tag_names(session).join(", "));
+        }
+        if let Some(end) = session.end {
+            println!("  Logged: {} - {}", session.start.format("%Y-%m-%d %H:%M"), end.format("%H:%M"));
+        }
+        if session.non_billable {
+            println!("  Not billable");
+        } else if let Some(rate) = data.rate_for(session).filter(|_| session.rate.is_some()) {
+            // Only a rate set on the session itself, shown with its category's currency
+            println!("  Rate: {} per hour", rate);
+        }
+        for note in notes {
+            println!("{}", note);
         }
-        println!("  Session will end at: {}", end_time.format("%H:%M:%S"));
 
         Ok(())
     }
 
+    fn apply_edit(data: &MetronData, old: &Session, changes: SessionChanges, notes: &mut Vec<String>) -> Result<Session> {
+        let mut session = old.clone();
+
+        if let Some(title) = changes.title {
+            session.title = title;
+        }
+        if let Some(category) = changes.category {
+            let category = data.find_category(&category).ok_or(MetronError::CategoryNotFound)?;
+            if category.archived {
+                return Err(MetronError::CategoryArchived);
+            }
+            session.category_id = category.id.clone();
+        }
+        if changes.clear_tags {
+            session.tag_ids.clear();
+        } else if !changes.tags.is_empty() {
+            session.tag_ids = Self::resolve_tags(data, &changes.tags)?;
+        }
+        if let Some(start) = changes.start {
+            if start > Utc::now() {
+                return Err(MetronError::StartInFuture);
+            }
+            session.start = start;
+        }
+        if let Some(duration) = changes.duration {
+            session.duration = duration;
+        }
+        if changes.clear_rate {
+            session.rate = None;
+        } else if changes.rate.is_some() {
+            session.rate = changes.rate;
+        }
+        if let Some(billable) = changes.billable {
+            session.non_billable = !billable;
+        }
+
+        let moved = session.category_id != old.category_id
+            || session.start != old.start
+            || session.duration != old.duration;
+        if moved {
+            let timeblock = data.timeblock_for(&session.category_id);
+            if !timeblock.is_valid(session.duration) {
+                return Err(MetronError::InvalidDuration(timeblock.block_minutes));
+            }
+            Self::check_category_quota(data, &session.category_id, session.start, session.duration, notes)?;
+            session.end = Some(session.start + chrono::Duration::minutes(session.duration as i64));
+        }
+
+        data.check_tag_groups(&session.tag_ids)?;
+        data.check_tag_requirement(&session.category_id, &session.tag_ids)?;
+        Ok(session)
+    }
+
+    /// Looks up tags by name, dropping duplicates
+    fn resolve_tags(data: &MetronData, tags: &[String]) -> Result<Vec<String>> {
+        let mut tag_ids: Vec<String> = Vec::new();
+        for tag in tags {
+            let id = data.find_tag(tag)
+                .map(|t| t.id.clone())
+                .ok_or(MetronError::TagNotFound)?;
+            if !tag_ids.contains(&id) {
+                tag_ids.push(id);
+            }
+        }
+        Ok(tag_ids)
+    }
+
+    /// Adds warnings for working-time rules broken on `date`, if enabled
+    fn check_compliance(data: &MetronData, date: NaiveDate, notes: &mut Vec<String>) {
+        if !data.settings.compliance.warn_on_session {
+            return;
+        }
+
+        // Session times are given in UTC, so are their days
+        let day = compliance_manager::check_days(data, Tz::UTC).into_iter().find(|d| d.date == date);
+        for violation in day.map(|d| d.violations).unwrap_or_default() {
+            notes.push(format!("⚠ Working-time rule on {}: {}", date.format("%Y-%m-%d"), violation));
+        }
+    }
+
+    /// Applies the configured quota policy to a session about to be logged
+    fn check_category_quota(data: &MetronData, category_id: &str, start: DateTime<Utc>, duration: u32, notes: &mut Vec<String>) -> Result<()> {
+        let policy = data.settings.quota_policy;
+        if policy == QuotaPolicy::Ignore {
+            return Ok(());
+        }
+
+        // Time logged in a subcategory also counts toward every parent's quota
+        let mut exceeded = Vec::new();
+        let mut nearest_remaining = None;
+        for category in data.ancestors(category_id) {
+            let quota_minutes = category.category_weekly_quota;
+            if quota_minutes == 0 {
+                continue;
+            }
+
+            let used = data.category_minutes_in_week(&category.id, start);
+            let remaining = quota_minutes.saturating_sub(used);
+            let overtime = (used + duration).saturating_sub(quota_minutes).min(duration);
+
+            if overtime > 0 {
+                exceeded.push((data.category_path(&category.id), quota_minutes, remaining, overtime));
+            } else if nearest_remaining.is_none() {
+                nearest_remaining = Some((remaining - duration, quota_minutes));
+            }
+        }
+
+        if exceeded.is_empty() {
+            if let Some((remaining, quota_minutes)) = nearest_remaining {
+                notes.push(format!("  Quota: {} of {} remaining this week",
+                    format_duration(remaining), format_duration(quota_minutes)));
+            }
+            return Ok(());
+        }
+
+        if policy == QuotaPolicy::Block {
+            for (category, quota_minutes, remaining, overtime) in &exceeded {
+                println!("✗ Category '{}' has {} of its {} weekly quota remaining",
+                    category, format_duration(*remaining), format_duration(*quota_minutes));
+                println!("  {} of the {} would be overtime", format_duration(*overtime), format_duration(duration));
+            }
+            return Err(MetronError::QuotaExceeded);
+        }
+
+        for (category, quota_minutes, remaining, overtime) in exceeded {
+            notes.push(format!("⚠ Category '{}' had {} of its {} weekly quota remaining",
+                category, format_duration(remaining), format_duration(quota_minutes)));
+            notes.push(format!("  {} of the {} count as overtime", format_duration(overtime), format_duration(duration)));
+        }
+        Ok(())
+    }
+
     pub fn end_session(&mut self, id: String) -> Result<()> {
         let data = self.storage.get_data_mut();
         
-        let session = data.sessions.iter_mut()
-            .find(|s| s.id == id)
+        let index = data.sessions.iter()
+            .position(|s| s.id == id)
             .ok_or(MetronError::SessionNotFound)?;
 
+        // Round to the category's timeblock
+        let timeblock = data.timeblock_for(&data.sessions[index].category_id);
+        let session = &mut data.sessions[index];
+
         let now = Utc::now();
-        let actual_duration = now.signed_duration_since(session.start).num_minutes() as u32;
-        
-        // Round down to nearest 15 minutes
-        let rounded_duration = (actual_duration / 15) * 15;
+        let actual_duration = u32::try_from(now.signed_duration_since(session.start).num_minutes())
+            .map_err(|_| MetronError::StartInFuture)?;
+        let rounded_duration = timeblock.round(actual_duration);
         
         session.end = Some(now);
         session.duration = rounded_duration;
+
+        let mut notes = Vec::new();
+        let date = session.start.date_naive();
+        Self::check_compliance(data, date, &mut notes);
         
         self.storage.save()?;
 
-        if rounded_duration < actual_duration {
-            println!("✓ Session ended early. Duration rounded down: {}min → {}min", actual_duration, rounded_duration);
+        if rounded_duration != actual_duration {
+            println!("✓ Session ended early. Duration rounded {} to {}min blocks: {} → {}",
+                timeblock.rounding, timeblock.block_minutes, format_duration(actual_duration), format_duration(rounded_duration));
         } else {
-            println!("✓ Session completed: {}min", rounded_duration);
+            println!("✓ Session completed: {}", format_duration(rounded_duration));
+        }
+        for note in notes {
+            println!("{}", note);
         }
 
         Ok(())
@@ -99,17 +376,21 @@ impl<'a> SessionManager<'a> {
         for session in &data.sessions {
             let short_id = &session.id[..8];
             let start_time = session.start.format("%Y-%m-%d %H:%M");
-            let tags_str = if session.tags.is_empty() { 
+            let tags_str = if session.tag_ids.is_empty() { 
                 "-".to_string() 
             } else { 
-                session.tags.join(", ") 
+                data.painted_tag_names(session).join(", ") 
             };
+            let category = paint(
+                &format!("{:<15}", data.category_path(&session.category_id)),
+                data.category_color(&session.category_id),
+            );
             
-            println!("{:<8} {:<25} {:<15} {:<10}min {:<20} {:<20}", 
+            println!("{:<8} {:<25} {} {:<10} {:<20} {:<20}", 
                 short_id, 
                 session.title, 
-                session.category, 
-                session.duration,
+                category, 
+                format_duration(session.duration),
                 start_time,
                 tags_str
             );
@@ -148,3 +429,53 @@ impl<'a> SessionManager<'a> {
         Ok(())
     }
 }
+
+/// Number of tags suggested for a session started without any
+const SUGGESTED_TAGS: usize = 3;
+
+/// Names of the tags most used on earlier sessions in the same category or
+/// with similar titles, best first. If the category requires a tag of a
+/// group, only that group's tags are suggested.
+fn suggest_tags(data: &MetronData, category_id: &str, title: &str) -> Vec<String> {
+    let words = title_words(title);
+    let required_group = match data.tag_requirement_for(category_id) {
+        TagRequirement::Group(group_id) => Some(group_id),
+        _ => None,
+    };
+
+    let mut scores: HashMap<&str, f64> = HashMap::new();
+    for session in &data.sessions {
+        let mut weight = if session.category_id == category_id { 1.0 } else { 0.0 };
+
+        // Share of words the two titles have in common
+        let other = title_words(&session.title);
+        let union = words.union(&other).count();
+        if union > 0 {
+            weight += 2.0 * words.intersection(&other).count() as f64 / union as f64;
+        }
+        if weight == 0.0 {
+            continue;
+        }
+
+        for tag_id in &session.tag_ids {
+            *scores.entry(tag_id.as_str()).or_default() += weight;
+        }
+    }
+
+    let mut ranked: Vec<(&Tag, f64)> = scores.into_iter()
+        .filter_map(|(id, score)| data.tags.iter().find(|t| t.id == id).map(|t| (t, score)))
+        .filter(|(t, _)| required_group.is_none_or(|g| t.group_id.as_ref() == Some(g)))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.name.cmp(&b.0.name)));
+    ranked.into_iter()
+        .take(SUGGESTED_TAGS)
+        .map(|(t, _)| paint(&t.name, t.color.as_deref()))
+        .collect()
+}
+
+fn title_words(title: &str) -> HashSet<String> {
+    title.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| w.len() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
@@ -1,5 +1,7 @@
-use crate::models::{Tag, MetronError, Result};
+use crate::models::{names_match, MetronData, Rate, Tag, TagGroup, MetronError, Result};
 use crate::storage::Storage;
+use crate::utils::{non_empty, paint};
+use uuid::Uuid;
 
 pub struct TagManager<'a> {
     storage: &'a mut Storage,
@@ -10,27 +12,42 @@ impl<'a> TagManager<'a> {
         Self { storage }
     }
 
-    pub fn create_tag(&mut self, name: String) -> Result<()> {
+    pub fn create_tag(&mut self, name: String, group: Option<String>, description: Option<String>, color: Option<String>) -> Result<()> {
         let data = self.storage.get_data_mut();
         
         // Check if tag already exists
-        if data.tags.iter().any(|t| t.name == name) {
+        if data.find_tag(&name).is_some() {
             return Err(MetronError::DuplicateName);
         }
 
-        // Check tag limit (max 7 tags)
-        if data.tags.len() >= 7 {
-            return Err(MetronError::TagLimitExceeded);
+        // Check the configured tag limit
+        if let Some(limit) = data.settings.tag_limit {
+            if data.tags.len() >= limit as usize {
+                return Err(MetronError::TagLimitExceeded(limit));
+            }
         }
 
+        let group = match group {
+            Some(group) => Some(data.find_tag_group(&group).cloned().ok_or(MetronError::TagGroupNotFound)?),
+            None => None,
+        };
+
         let tag = Tag {
+            id: Uuid::new_v4().to_string(),
             name: name.clone(),
+            group_id: group.as_ref().map(|g| g.id.clone()),
+            description: description.and_then(non_empty),
+            color: color.and_then(non_empty),
+            rate: None,
         };
 
         data.tags.push(tag);
         self.storage.save()?;
 
-        println!("✓ Created tag '{}'", name);
+        match group {
+            Some(group) => println!("✓ Created tag '{}' in group '{}'", name, group.name),
+            None => println!("✓ Created tag '{}'", name),
+        }
         Ok(())
     }
 
@@ -42,11 +59,140 @@ impl<'a> TagManager<'a> {
             return Ok(());
         }
 
-        println!("Tags ({}/7):", data.tags.len());
-        for (i, tag) in data.tags.iter().enumerate() {
-            println!("{}. {}", i + 1, tag.name);
+        match data.settings.tag_limit {
+            Some(limit) => println!("Tags ({}/{}):", data.tags.len(), limit),
+            None => println!("Tags ({}):", data.tags.len()),
+        }
+        let ungrouped = data.tags.iter().filter(|t| t.group_id.as_ref().is_none_or(|g| data.tag_group(g).is_none()));
+        for (i, tag) in ungrouped.enumerate() {
+            println!("{}. {}", i + 1, describe(tag));
+        }
+
+        for group in &data.tag_groups {
+            let choice = if group.single_choice { " (single choice)" } else { "" };
+            println!("{}{}:", group.name, choice);
+            let tags = data.tags.iter().filter(|t| t.group_id.as_deref() == Some(group.id.as_str()));
+            for (i, tag) in tags.enumerate() {
+                println!("  {}. {}", i + 1, describe(tag));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Changes a tag's description or color; empty values remove them
+    pub fn update_tag(&mut self, name: String, description: Option<String>, color: Option<String>) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let tag = data.tags.iter_mut()
+            .find(|t| names_match(&t.name, &name))
+            .ok_or(MetronError::TagNotFound)?;
+        if let Some(description) = description {
+            tag.description = non_empty(description);
         }
+        if let Some(color) = color {
+            tag.color = non_empty(color);
+        }
+        let tag = tag.clone();
+        self.storage.save()?;
+
+        println!("✓ Updated tag {}", describe(&tag));
+        Ok(())
+    }
 
+    /// Sets the hourly rate of sessions with the tag, overriding their category's
+    pub fn set_rate(&mut self, name: String, rate: Option<Rate>) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let tag = data.tags.iter_mut()
+            .find(|t| names_match(&t.name, &name))
+            .ok_or(MetronError::TagNotFound)?;
+        tag.rate = rate;
+        let tag = tag.clone();
+        self.storage.save()?;
+
+        match &tag.rate {
+            Some(rate) => println!("✓ Sessions tagged '{}' are now billed at {} per hour", tag.name, rate),
+            None => println!("✓ Sessions tagged '{}' are now billed at their category's rate", tag.name),
+        }
+        Ok(())
+    }
+
+    /// Renames a tag; sessions reference it by ID and follow automatically
+    pub fn rename_tag(&mut self, name: String, new_name: String) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let new_name = new_name.trim().to_string();
+        if new_name.is_empty() || new_name.contains('/') {
+            return Err(MetronError::InvalidName);
+        }
+
+        let id = data.find_tag(&name)
+            .map(|t| t.id.clone())
+            .ok_or(MetronError::TagNotFound)?;
+
+        // Changing only the case of the name is allowed
+        if data.find_tag(&new_name).is_some_and(|t| t.id != id) {
+            return Err(MetronError::DuplicateName);
+        }
+
+        let tag = data.tags.iter_mut()
+            .find(|t| t.id == id)
+            .ok_or(MetronError::TagNotFound)?;
+        let old_name = std::mem::replace(&mut tag.name, new_name.clone());
+
+        let sessions = data.sessions.iter().filter(|s| s.tag_ids.contains(&id)).count();
+        self.storage.save()?;
+
+        println!("✓ Renamed tag '{}' to '{}' ({} sessions)", old_name, new_name, sessions);
+        Ok(())
+    }
+
+    /// Replaces `from` with `into` on every session, dropping duplicates, and removes `from`.
+    /// Refused if a session would break its category's tag rule or a single-choice group.
+    pub fn merge_tags(&mut self, from: String, into: String) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let source = data.find_tag(&from).cloned().ok_or(MetronError::TagNotFound)?;
+        let target = data.find_tag(&into).cloned().ok_or(MetronError::TagNotFound)?;
+        if source.id == target.id {
+            return Err(MetronError::MergeIntoSelf);
+        }
+
+        let mut merged: Vec<(usize, Vec<String>)> = Vec::new();
+        let mut broken = Vec::new();
+        for (index, session) in data.sessions.iter().enumerate().filter(|(_, s)| s.tag_ids.contains(&source.id)) {
+            let mut tag_ids: Vec<String> = Vec::with_capacity(session.tag_ids.len());
+            for id in &session.tag_ids {
+                let id = if *id == source.id { &target.id } else { id };
+                if !tag_ids.contains(id) {
+                    tag_ids.push(id.clone());
+                }
+            }
+            let check = data.check_tag_groups(&tag_ids)
+                .and_then(|_| data.check_tag_requirement(&session.category_id, &tag_ids));
+            match check {
+                Ok(()) => merged.push((index, tag_ids)),
+                Err(e) => broken.push((session, e)),
+            }
+        }
+        if !broken.is_empty() {
+            println!("✗ Merging '{}' into '{}' would break {} sessions:", source.name, target.name, broken.len());
+            for (session, error) in &broken {
+                println!("  {} {} ({}): {}", &session.id[..8], session.title, session.start.format("%Y-%m-%d"), error);
+            }
+            return Err(broken.swap_remove(0).1);
+        }
+
+        let session_count = merged.len();
+        for (index, tag_ids) in merged {
+            data.sessions[index].tag_ids = tag_ids;
+        }
+        data.tags.retain(|t| t.id != source.id);
+
+        self.storage.save()?;
+
+        println!("✓ Merged tag '{}' into '{}' ({} sessions updated)", source.name, target.name, session_count);
         Ok(())
     }
 
@@ -54,12 +200,13 @@ impl<'a> TagManager<'a> {
         let data = self.storage.get_data_mut();
         
         let index = data.tags.iter()
-            .position(|t| t.name == name)
+            .position(|t| names_match(&t.name, &name))
             .ok_or(MetronError::TagNotFound)?;
+        let id = data.tags[index].id.clone();
 
         // Check if any sessions use this tag
         let sessions_using_tag = data.sessions.iter()
-            .any(|s| s.tags.contains(&name));
+            .any(|s| s.tag_ids.contains(&id));
         
         if sessions_using_tag {
             println!("Warning: Tag '{}' is used by existing sessions.", name);
@@ -80,4 +227,120 @@ impl<'a> TagManager<'a> {
         println!("✓ Deleted tag '{}'", name);
         Ok(())
     }
+
+    pub fn create_tag_group(&mut self, name: String, single_choice: bool) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        if data.find_tag_group(&name).is_some() {
+            return Err(MetronError::DuplicateName);
+        }
+
+        data.tag_groups.push(TagGroup {
+            id: Uuid::new_v4().to_string(),
+            name: name.clone(),
+            single_choice,
+        });
+        self.storage.save()?;
+
+        let choice = if single_choice { " (single choice)" } else { "" };
+        println!("✓ Created tag group '{}'{}", name, choice);
+        Ok(())
+    }
+
+    /// Changes whether sessions may use more than one tag of the group
+    pub fn update_tag_group(&mut self, name: String, single_choice: bool) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let group = data.tag_groups.iter_mut()
+            .find(|g| names_match(&g.name, &name))
+            .ok_or(MetronError::TagGroupNotFound)?;
+        group.single_choice = single_choice;
+        let group = group.clone();
+
+        let conflicts = conflicting_sessions(data, &group);
+        self.storage.save()?;
+
+        if single_choice {
+            println!("✓ Sessions can now use only one tag of group '{}'", group.name);
+        } else {
+            println!("✓ Sessions can now use any tags of group '{}'", group.name);
+        }
+        if conflicts > 0 {
+            println!("⚠ {} existing sessions use more than one tag of this group", conflicts);
+        }
+        Ok(())
+    }
+
+    /// Moves a tag into a group, or out of its group if `group` is `None`
+    pub fn set_tag_group(&mut self, tag: String, group: Option<String>) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let group = match group {
+            Some(group) => Some(data.find_tag_group(&group).cloned().ok_or(MetronError::TagGroupNotFound)?),
+            None => None,
+        };
+        let tag = data.tags.iter_mut()
+            .find(|t| names_match(&t.name, &tag))
+            .ok_or(MetronError::TagNotFound)?;
+        tag.group_id = group.as_ref().map(|g| g.id.clone());
+        let tag_name = tag.name.clone();
+
+        let conflicts = group.as_ref().map(|g| conflicting_sessions(data, g)).unwrap_or(0);
+        self.storage.save()?;
+
+        match group {
+            Some(group) => println!("✓ Moved tag '{}' into group '{}'", tag_name, group.name),
+            None => println!("✓ Removed tag '{}' from its group", tag_name),
+        }
+        if conflicts > 0 {
+            println!("⚠ {} existing sessions use more than one tag of this group", conflicts);
+        }
+        Ok(())
+    }
+
+    /// Deletes a group; its tags are kept without a group
+    pub fn delete_tag_group(&mut self, name: String) -> Result<()> {
+        let data = self.storage.get_data_mut();
+
+        let index = data.tag_groups.iter()
+            .position(|g| names_match(&g.name, &name))
+            .ok_or(MetronError::TagGroupNotFound)?;
+        let group = data.tag_groups.remove(index);
+
+        let mut tag_count = 0;
+        for tag in data.tags.iter_mut().filter(|t| t.group_id.as_deref() == Some(group.id.as_str())) {
+            tag.group_id = None;
+            tag_count += 1;
+        }
+        self.storage.save()?;
+
+        println!("✓ Deleted tag group '{}' ({} tags ungrouped)", group.name, tag_count);
+        Ok(())
+    }
+}
+
+/// Tag name in its color, followed by its description
+fn describe(tag: &Tag) -> String {
+    let mut name = paint(&tag.name, tag.color.as_deref());
+    if let Some(rate) = &tag.rate {
+        name = format!("{} ({}/h)", name, rate);
+    }
+    match &tag.description {
+        Some(description) => format!("{} - {}", name, description),
+        None => name,
+    }
+}
+
+/// Number of sessions using more than one tag of a single-choice group
+fn conflicting_sessions(data: &MetronData, group: &TagGroup) -> usize {
+    if !group.single_choice {
+        return 0;
+    }
+    let group_tags: Vec<&String> = data.tags.iter()
+        .filter(|t| t.group_id.as_deref() == Some(group.id.as_str()))
+        .map(|t| &t.id)
+        .collect();
+    data.sessions.iter()
+        .filter(|s| s.tag_ids.iter().filter(|id| group_tags.contains(id)).count() > 1)
+        .count()
 }
@@ -1,51 +1,656 @@
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
+use clap::ValueEnum;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Category {
+    pub id: String,
     pub name: String,
-    pub category_weekly_quota: u32, // in hours
+    pub category_weekly_quota: u32, // in minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category_weekly_target: Option<u32>, // minimum minutes per week
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub block_minutes: Option<u32>, // overrides the global timeblock size
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rounding: Option<Rounding>, // overrides the global rounding mode
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub archived: bool, // hidden, no new sessions, quota not counted
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tag_requirement: Option<TagRequirement>, // overrides the global rule
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>, // color name or #rrggbb
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Rate>, // also used by subcategories without their own
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Tag {
+    pub id: String,
     pub name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub color: Option<String>, // color name or #rrggbb
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Rate>, // overrides the category's rate
+}
+
+/// A named set of tags, e.g. "activity" with build/research
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TagGroup {
+    pub id: String,
+    pub name: String,
+    #[serde(default)]
+    pub single_choice: bool, // at most one tag of the group per session
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Session {
     pub id: String,
     pub title: String,
-    pub category: String,
-    pub tags: Vec<String>,
+    pub category_id: String,
+    pub tag_ids: Vec<String>,
     pub start: DateTime<Utc>,
     pub end: Option<DateTime<Utc>>,
     pub duration: u32, // in minutes
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub import_key: Option<String>, // identifies the record it was imported from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rate: Option<Rate>, // overrides the rates of its tags and category
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub non_billable: bool,
+}
+
+/// An hourly rate in cents (or other minor units); without a currency the
+/// currency of the category's rate applies
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rate {
+    pub cents: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub currency: Option<String>,
 }
 
+impl std::fmt::Display for Rate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", crate::utils::format_money(self.cents, self.currency.as_deref()))
+    }
+}
+
+/// Version of the data file layout, see `storage::migrate`
+pub const DATA_VERSION: u32 = 2;
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct MetronData {
+    #[serde(default)]
+    pub version: u32,
     pub categories: Vec<Category>,
     pub tags: Vec<Tag>,
+    #[serde(default)]
+    pub tag_groups: Vec<TagGroup>,
     pub sessions: Vec<Session>,
-    pub total_weekly_quota: Option<u32>, // in hours
+    pub total_weekly_quota: Option<u32>, // in minutes
+    #[serde(default)]
+    pub settings: Settings,
+}
+
+/// What to do when a new session would push a category past its weekly quota
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum QuotaPolicy {
+    /// Log the session without checking the quota
+    Ignore,
+    /// Log the session but print how much of it becomes overtime
+    #[default]
+    Warn,
+    /// Refuse sessions that would exceed the quota
+    Block,
+}
+
+impl std::fmt::Display for QuotaPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuotaPolicy::Ignore => write!(f, "ignore"),
+            QuotaPolicy::Warn => write!(f, "warn"),
+            QuotaPolicy::Block => write!(f, "block"),
+        }
+    }
+}
+
+/// How quotas and targets are combined when one category is merged into another
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum MergeRule {
+    /// Add the source's quota to the target's
+    Sum,
+    /// Keep the target's quota
+    Keep,
+    /// Use the larger of the two
+    Max,
+}
+
+impl MergeRule {
+    pub fn combine(&self, from: u32, into: u32) -> u32 {
+        match self {
+            MergeRule::Sum => from + into,
+            MergeRule::Keep => into,
+            MergeRule::Max => from.max(into),
+        }
+    }
+}
+
+/// Which tags a session must have
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum TagRequirement {
+    #[default]
+    None,
+    /// At least one tag
+    Any,
+    /// At least one tag of the group with this ID
+    Group(String),
+}
+
+/// Block sizes sessions can be tracked in
+pub const BLOCK_SIZES: [u32; 5] = [5, 6, 10, 15, 30];
+
+/// How the duration of a session ended early is rounded to the timeblock
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum Rounding {
+    #[default]
+    Down,
+    Nearest,
+    Up,
+    /// Keep the exact minutes
+    None,
+}
+
+impl std::fmt::Display for Rounding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rounding::Down => write!(f, "down"),
+            Rounding::Nearest => write!(f, "nearest"),
+            Rounding::Up => write!(f, "up"),
+            Rounding::None => write!(f, "none"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Timeblock {
+    pub block_minutes: u32,
+    pub rounding: Rounding,
+}
+
+impl Default for Timeblock {
+    fn default() -> Self {
+        Self { block_minutes: 15, rounding: Rounding::Down }
+    }
+}
+
+impl Timeblock {
+    /// Whether a planned duration fits the timeblock; without rounding any positive duration does
+    pub fn is_valid(&self, minutes: u32) -> bool {
+        minutes > 0 && (self.rounding == Rounding::None || minutes.is_multiple_of(self.block_minutes))
+    }
+
+    pub fn round(&self, minutes: u32) -> u32 {
+        let block = self.block_minutes;
+        match self.rounding {
+            Rounding::Down => (minutes / block) * block,
+            Rounding::Nearest => ((minutes + block / 2) / block) * block,
+            Rounding::Up => minutes.div_ceil(block) * block,
+            Rounding::None => minutes,
+        }
+    }
+}
+
+impl std::fmt::Display for Timeblock {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}min, round {}", self.block_minutes, self.rounding)
+    }
+}
+
+/// A break of at least `break_minutes` is required once more than `after_minutes` are worked in a day
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct BreakRule {
+    pub after_minutes: u32,
+    pub break_minutes: u32,
+}
+
+/// Working-time limits checked by `metron compliance`, defaulting to the German Arbeitszeitgesetz
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComplianceRules {
+    pub max_daily_minutes: u32,
+    pub breaks: Vec<BreakRule>,
+    pub min_break_segment_minutes: u32, // shorter gaps don't count as a break
+    pub min_rest_minutes: u32,          // between the end of one day and the start of the next
+    #[serde(default)]
+    pub warn_on_session: bool,
+}
+
+impl Default for ComplianceRules {
+    fn default() -> Self {
+        Self {
+            max_daily_minutes: 600,
+            breaks: vec![
+                BreakRule { after_minutes: 360, break_minutes: 30 },
+                BreakRule { after_minutes: 540, break_minutes: 45 },
+            ],
+            min_break_segment_minutes: 15,
+            min_rest_minutes: 660,
+            warn_on_session: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub quota_policy: QuotaPolicy,
+    #[serde(default)]
+    pub compliance: ComplianceRules,
+    #[serde(default)]
+    pub timeblock: Timeblock,
+    #[serde(default = "default_tag_limit")]
+    pub tag_limit: Option<u32>, // None allows any number of tags
+    #[serde(default)]
+    pub tag_requirement: TagRequirement,
+}
+
+/// Number of tags allowed unless configured otherwise
+pub const DEFAULT_TAG_LIMIT: u32 = 7;
+
+fn default_tag_limit() -> Option<u32> {
+    Some(DEFAULT_TAG_LIMIT)
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            quota_policy: QuotaPolicy::default(),
+            compliance: ComplianceRules::default(),
+            timeblock: Timeblock::default(),
+            tag_limit: default_tag_limit(),
+            tag_requirement: TagRequirement::default(),
+        }
+    }
 }
 
 impl MetronData {
     pub fn new() -> Self {
-        Self::default()
+        Self {
+            version: DATA_VERSION,
+            ..Self::default()
+        }
+    }
+
+    /// Looks up a category by path (`Acme/Website/Backend`), ignoring case.
+    /// A bare name matches a top-level category, or else a unique subcategory.
+    pub fn find_category(&self, path: &str) -> Option<&Category> {
+        self.find_category_id(path).and_then(|id| self.category(&id))
+    }
+
+    pub fn find_category_mut(&mut self, path: &str) -> Option<&mut Category> {
+        let id = self.find_category_id(path)?;
+        self.categories.iter_mut().find(|c| c.id == id)
+    }
+
+    fn find_category_id(&self, path: &str) -> Option<String> {
+        if path.contains('/') {
+            let mut parent: Option<&Category> = None;
+            for segment in path.split('/').map(str::trim) {
+                parent = Some(self.child_named(parent.map(|p| p.id.as_str()), segment)?);
+            }
+            return parent.map(|c| c.id.clone());
+        }
+
+        if let Some(category) = self.child_named(None, path) {
+            return Some(category.id.clone());
+        }
+        let mut matches = self.categories.iter().filter(|c| names_match(&c.name, path));
+        match (matches.next(), matches.next()) {
+            (Some(c), None) => Some(c.id.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn category(&self, id: &str) -> Option<&Category> {
+        self.categories.iter().find(|c| c.id == id)
+    }
+
+    /// The direct child of `parent_id` (or top-level category) with the given name
+    pub fn child_named(&self, parent_id: Option<&str>, name: &str) -> Option<&Category> {
+        self.categories.iter()
+            .find(|c| c.parent_id.as_deref() == parent_id && names_match(&c.name, name))
+    }
+
+    pub fn children<'a>(&'a self, parent_id: Option<&'a str>) -> impl Iterator<Item = &'a Category> {
+        self.categories.iter().filter(move |c| c.parent_id.as_deref() == parent_id)
+    }
+
+    /// The category itself followed by its parent, grandparent and so on
+    pub fn ancestors(&self, id: &str) -> Vec<&Category> {
+        let mut chain = Vec::new();
+        let mut current = self.category(id);
+        while let Some(category) = current {
+            // Guard against cycles in hand-edited files
+            if chain.iter().any(|c: &&Category| c.id == category.id) {
+                break;
+            }
+            chain.push(category);
+            current = category.parent_id.as_deref().and_then(|p| self.category(p));
+        }
+        chain
+    }
+
+    /// IDs of the category and everything below it
+    pub fn subtree_ids(&self, id: &str) -> Vec<String> {
+        let mut ids = vec![id.to_string()];
+        let mut i = 0;
+        while i < ids.len() {
+            let children: Vec<String> = self.children(Some(&ids[i]))
+                .map(|c| c.id.clone())
+                .filter(|c| !ids.contains(c))
+                .collect();
+            ids.extend(children);
+            i += 1;
+        }
+        ids
+    }
+
+    /// Categories in tree order (each parent followed by its children) with their depth
+    pub fn category_tree(&self) -> Vec<(&Category, usize)> {
+        let mut result = Vec::new();
+        let mut stack: Vec<(&Category, usize)> = self.children(None).map(|c| (c, 0)).collect();
+        stack.reverse();
+        while let Some((category, depth)) = stack.pop() {
+            result.push((category, depth));
+            let mut children: Vec<_> = self.children(Some(&category.id)).map(|c| (c, depth + 1)).collect();
+            children.reverse();
+            stack.extend(children);
+        }
+        result
+    }
+
+    /// Looks up a tag by name, ignoring case
+    pub fn find_tag(&self, name: &str) -> Option<&Tag> {
+        self.tags.iter().find(|t| names_match(&t.name, name))
+    }
+
+    /// Full path of a category, e.g. `Acme/Website/Backend`; unknown IDs are shown as they are
+    pub fn category_path(&self, id: &str) -> String {
+        let chain = self.ancestors(id);
+        if chain.is_empty() {
+            return id.to_string();
+        }
+        chain.iter().rev().map(|c| c.name.as_str()).collect::<Vec<_>>().join("/")
+    }
+
+    /// Display name of a tag; unknown IDs are shown as they are
+    pub fn tag_name<'a>(&'a self, id: &'a str) -> &'a str {
+        self.tags.iter()
+            .find(|t| t.id == id)
+            .map(|t| t.name.as_str())
+            .unwrap_or(id)
+    }
+
+    /// Looks up a tag group by name, ignoring case
+    pub fn find_tag_group(&self, name: &str) -> Option<&TagGroup> {
+        self.tag_groups.iter().find(|g| names_match(&g.name, name))
+    }
+
+    pub fn tag_group(&self, id: &str) -> Option<&TagGroup> {
+        self.tag_groups.iter().find(|g| g.id == id)
+    }
+
+    /// Fails if more than one tag of a single-choice group is used
+    pub fn check_tag_groups(&self, tag_ids: &[String]) -> Result<()> {
+        for group in self.tag_groups.iter().filter(|g| g.single_choice) {
+            let used = self.tags.iter()
+                .filter(|t| t.group_id.as_deref() == Some(group.id.as_str()) && tag_ids.contains(&t.id))
+                .count();
+            if used > 1 {
+                return Err(MetronError::TagGroupConflict(group.name.clone()));
+            }
+        }
+        Ok(())
+    }
+
+    /// Tag rule for a category, inherited from the nearest ancestor that
+    /// overrides it and falling back to the global setting
+    pub fn tag_requirement_for(&self, category_id: &str) -> &TagRequirement {
+        self.ancestors(category_id).iter()
+            .find_map(|c| c.tag_requirement.as_ref())
+            .unwrap_or(&self.settings.tag_requirement)
+    }
+
+    /// Fails if the tags don't satisfy the category's tag rule
+    pub fn check_tag_requirement(&self, category_id: &str, tag_ids: &[String]) -> Result<()> {
+        match self.tag_requirement_for(category_id) {
+            TagRequirement::None => Ok(()),
+            TagRequirement::Any if tag_ids.is_empty() => Err(MetronError::MissingTag(None)),
+            TagRequirement::Any => Ok(()),
+            TagRequirement::Group(group_id) => {
+                let satisfied = self.tags.iter()
+                    .any(|t| t.group_id.as_ref() == Some(group_id) && tag_ids.contains(&t.id));
+                if satisfied {
+                    Ok(())
+                } else {
+                    let group = self.tag_group(group_id).map(|g| g.name.clone()).unwrap_or_else(|| group_id.clone());
+                    Err(MetronError::MissingTag(Some(group)))
+                }
+            }
+        }
+    }
+
+    /// Builds a tag rule from command-line options: `off` requires nothing,
+    /// a group requires one of its tags, and otherwise any tag is required
+    pub fn tag_requirement_from(&self, group: Option<&str>, off: bool) -> Result<TagRequirement> {
+        if off {
+            return Ok(TagRequirement::None);
+        }
+        match group {
+            Some(group) => {
+                let group = self.find_tag_group(group).ok_or(MetronError::TagGroupNotFound)?;
+                Ok(TagRequirement::Group(group.id.clone()))
+            }
+            None => Ok(TagRequirement::Any),
+        }
+    }
+
+    /// Human-readable form of a tag rule
+    pub fn describe_tag_requirement(&self, requirement: &TagRequirement) -> String {
+        match requirement {
+            TagRequirement::None => "no tags".to_string(),
+            TagRequirement::Any => "at least one tag".to_string(),
+            TagRequirement::Group(id) => {
+                let group = self.tag_group(id).map(|g| g.name.as_str()).unwrap_or(id);
+                format!("at least one tag of group '{}'", group)
+            }
+        }
     }
+
+    /// Display names of a session's tags in their colors
+    pub fn painted_tag_names(&self, session: &Session) -> Vec<String> {
+        session.tag_ids.iter()
+            .map(|id| crate::utils::paint(self.tag_name(id), self.tag_color(id)))
+            .collect()
+    }
+
+    pub fn tag_color(&self, id: &str) -> Option<&str> {
+        self.tags.iter().find(|t| t.id == id).and_then(|t| t.color.as_deref())
+    }
+
+    /// Color of a category, inherited from the nearest ancestor that has one
+    pub fn category_color(&self, id: &str) -> Option<&str> {
+        self.ancestors(id).into_iter().find_map(|c| c.color.as_deref())
+    }
+
+    /// Quota taken up by active top-level categories against the total weekly quota
+    pub fn committed_quota(&self) -> u32 {
+        committed_quotas(&self.categories).sum_roots
+    }
+
+    /// Timeblock rules for a category, inherited from the nearest ancestor
+    /// that overrides them and falling back to the global settings
+    pub fn timeblock_for(&self, category_id: &str) -> Timeblock {
+        let global = self.settings.timeblock;
+        let chain = self.ancestors(category_id);
+        Timeblock {
+            block_minutes: chain.iter().find_map(|c| c.block_minutes).unwrap_or(global.block_minutes),
+            rounding: chain.iter().find_map(|c| c.rounding).unwrap_or(global.rounding),
+        }
+    }
+
+    /// Rate of a category, inherited from the nearest ancestor that has one
+    pub fn category_rate(&self, id: &str) -> Option<Rate> {
+        let rate = self.ancestors(id).into_iter().find_map(|c| c.rate.as_ref())?;
+        Some(self.with_currency(rate, id))
+    }
+
+    /// Tags of a session that have a rate of their own
+    pub fn rated_tags(&self, session: &Session) -> Vec<&Tag> {
+        self.tags.iter()
+            .filter(|t| t.rate.is_some() && session.tag_ids.contains(&t.id))
+            .collect()
+    }
+
+    /// Hourly rate a session is billed at: its own, else the highest rate of
+    /// its tags, else its category's. The order of the tags doesn't matter.
+    pub fn rate_for(&self, session: &Session) -> Option<Rate> {
+        let tag_rate = self.rated_tags(session).into_iter()
+            .filter_map(|t| t.rate.as_ref())
+            .max_by(|a, b| a.cents.cmp(&b.cents).then_with(|| b.currency.cmp(&a.currency)));
+        let rate = session.rate.as_ref().or(tag_rate)
+            .or_else(|| self.ancestors(&session.category_id).into_iter().find_map(|c| c.rate.as_ref()))?;
+        Some(self.with_currency(rate, &session.category_id))
+    }
+
+    /// Fills in the currency of the nearest category rate that has one
+    fn with_currency(&self, rate: &Rate, category_id: &str) -> Rate {
+        let currency = rate.currency.clone().or_else(|| {
+            self.ancestors(category_id).into_iter()
+                .find_map(|c| c.rate.as_ref().and_then(|r| r.currency.clone()))
+        });
+        Rate { cents: rate.cents, currency }
+    }
+
+    /// Minutes already logged in a category and its subcategories during the week containing `at`
+    pub fn category_minutes_in_week(&self, category_id: &str, at: DateTime<Utc>) -> u32 {
+        let (week_start, week_end) = crate::utils::week_bounds(at);
+        let ids = self.subtree_ids(category_id);
+        self.sessions.iter()
+            .filter(|s| ids.contains(&s.category_id))
+            .filter(|s| s.start >= week_start && s.start < week_end)
+            .map(|s| s.duration)
+            .sum()
+    }
+}
+
+struct CommittedQuotas {
+    by_id: HashMap<String, u32>,
+    sum_roots: u32,
+}
+
+/// How much quota each active category takes up in its parent: its own quota,
+/// or the sum of its children's if it has none
+fn committed_quotas(categories: &[Category]) -> CommittedQuotas {
+    fn visit(categories: &[Category], category: &Category, by_id: &mut HashMap<String, u32>) -> u32 {
+        let children: u32 = categories.iter()
+            .filter(|c| !c.archived && c.parent_id.as_deref() == Some(category.id.as_str()))
+            .map(|c| visit(categories, c, by_id))
+            .sum();
+        let committed = if category.category_weekly_quota > 0 { category.category_weekly_quota } else { children };
+        by_id.insert(category.id.clone(), committed);
+        committed
+    }
+
+    let mut by_id = HashMap::new();
+    let sum_roots = categories.iter()
+        .filter(|c| !c.archived && c.parent_id.is_none())
+        .map(|c| visit(categories, c, &mut by_id))
+        .sum();
+    CommittedQuotas { by_id, sum_roots }
+}
+
+/// A quota that is smaller than what its children (or, for the total weekly
+/// quota, the top-level categories) take up
+#[derive(Debug, Clone)]
+pub struct QuotaOverrun {
+    pub category_id: Option<String>, // None for the total weekly quota
+    pub quota: u32,
+    pub committed: u32,
+}
+
+/// Every category with a quota must fit its children's committed quotas,
+/// and the top level must fit into the total weekly quota
+pub fn quota_overruns(categories: &[Category], total_weekly_quota: Option<u32>) -> Vec<QuotaOverrun> {
+    let committed = committed_quotas(categories);
+    let mut overruns = Vec::new();
+
+    for parent in categories.iter().filter(|c| !c.archived && c.category_weekly_quota > 0) {
+        let children: u32 = categories.iter()
+            .filter(|c| c.parent_id.as_deref() == Some(parent.id.as_str()))
+            .filter_map(|c| committed.by_id.get(&c.id))
+            .sum();
+        if children > parent.category_weekly_quota {
+            overruns.push(QuotaOverrun {
+                category_id: Some(parent.id.clone()),
+                quota: parent.category_weekly_quota,
+                committed: children,
+            });
+        }
+    }
+
+    if let Some(total) = total_weekly_quota {
+        if committed.sum_roots > total {
+            overruns.push(QuotaOverrun { category_id: None, quota: total, committed: committed.sum_roots });
+        }
+    }
+    overruns
+}
+
+pub fn check_quota_tree(categories: &[Category], total_weekly_quota: Option<u32>) -> Result<()> {
+    if quota_overruns(categories, total_weekly_quota).is_empty() {
+        Ok(())
+    } else {
+        Err(MetronError::QuotaExceeded)
+    }
+}
+
+/// Category and tag names are matched case-insensitively
+pub fn names_match(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
 }
 
 #[derive(Debug)]
 pub enum MetronError {
     CategoryNotFound,
     TagNotFound,
+    TagGroupNotFound,
     SessionNotFound,
     QuotaExceeded,
-    InvalidDuration,
-    TagLimitExceeded,
+    InvalidDuration(u32),
+    StartInFuture,
+    TagLimitExceeded(u32),
+    TagGroupConflict(String),
+    MissingTag(Option<String>),
     DuplicateName,
+    MergeIntoSelf,
+    CategoryArchived,
+    CategoryHasChildren,
+    InvalidName,
+    ImportError(String),
+    ExportError(String),
+    TemplateError(String),
+    InvoiceError(String),
     StorageError(String),
 }
 
@@ -54,11 +659,24 @@ impl std::fmt::Display for MetronError {
         match self {
             MetronError::CategoryNotFound => write!(f, "Category not found"),
             MetronError::TagNotFound => write!(f, "Tag not found"),
+            MetronError::TagGroupNotFound => write!(f, "Tag group not found"),
             MetronError::SessionNotFound => write!(f, "Session not found"),
             MetronError::QuotaExceeded => write!(f, "Weekly quota would be exceeded"),
-            MetronError::InvalidDuration => write!(f, "Duration must be a multiple of 15 minutes"),
-            MetronError::TagLimitExceeded => write!(f, "Maximum of 7 tags allowed"),
+            MetronError::InvalidDuration(block) => write!(f, "Duration must be a multiple of {} minutes", block),
+            MetronError::StartInFuture => write!(f, "Session start is in the future"),
+            MetronError::TagLimitExceeded(limit) => write!(f, "Maximum of {} tags allowed", limit),
+            MetronError::MissingTag(None) => write!(f, "Sessions in this category need at least one tag"),
+            MetronError::MissingTag(Some(group)) => write!(f, "Sessions in this category need a tag of group '{}'", group),
+            MetronError::TagGroupConflict(group) => write!(f, "Only one tag of group '{}' can be used per session", group),
             MetronError::DuplicateName => write!(f, "Name already exists"),
+            MetronError::MergeIntoSelf => write!(f, "Cannot merge something into itself or one of its subcategories"),
+            MetronError::CategoryArchived => write!(f, "Category is archived"),
+            MetronError::CategoryHasChildren => write!(f, "Category has subcategories"),
+            MetronError::InvalidName => write!(f, "Names must not be empty or contain '/'"),
+            MetronError::ImportError(msg) => write!(f, "Import failed: {}", msg),
+            MetronError::ExportError(msg) => write!(f, "Export failed: {}", msg),
+            MetronError::TemplateError(msg) => write!(f, "Template error: {}", msg),
+            MetronError::InvoiceError(msg) => write!(f, "Invoice failed: {}", msg),
             MetronError::StorageError(msg) => write!(f, "Storage error: {}", msg),
         }
     }
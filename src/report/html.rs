@@ -0,0 +1,212 @@
+//! A single HTML file with inline CSS and SVG charts, so it can be emailed or
+//! opened anywhere without other files.
+
+use super::markdown::range_text;
+use super::{Report, Time};
+
+/// Colors for categories without one of their own
+const PALETTE: [&str; 8] = ["#4e79a7", "#f28e2b", "#59a14f", "#e15759", "#76b7b2", "#edc948", "#b07aa1", "#9c755f"];
+
+const STYLE: &str = "
+body { font-family: -apple-system, 'Segoe UI', Helvetica, Arial, sans-serif; color: #222; max-width: 960px; margin: 2em auto; padding: 0 1em; }
+h1 { margin-bottom: 0.2em; }
+h2 { margin-top: 2em; border-bottom: 1px solid #ddd; padding-bottom: 0.2em; }
+.summary { color: #555; }
+.summary strong { color: #222; }
+table { border-collapse: collapse; width: 100%; font-size: 0.9em; }
+th, td { padding: 0.35em 0.6em; border-bottom: 1px solid #eee; text-align: left; }
+th { background: #f6f6f6; }
+td.num, th.num { text-align: right; font-variant-numeric: tabular-nums; }
+tr.total td { font-weight: bold; border-top: 2px solid #ccc; }
+tr.empty td { color: #aaa; }
+.swatch { display: inline-block; width: 0.8em; height: 0.8em; border-radius: 2px; margin-right: 0.4em; vertical-align: baseline; }
+.legend span { margin-right: 1.2em; }
+svg text { font-size: 10px; fill: #666; }
+footer { margin-top: 3em; color: #999; font-size: 0.8em; }
+";
+
+pub fn render(report: &Report) -> String {
+    let colors: Vec<String> = report.columns.iter().enumerate()
+        .map(|(i, path)| {
+            report.categories.iter()
+                .find(|c| c.depth == 0 && &c.path == path)
+                .and_then(|c| c.color.clone())
+                .unwrap_or_else(|| PALETTE[i % PALETTE.len()].to_string())
+        })
+        .collect();
+
+    let mut html = String::new();
+    html.push_str("<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n");
+    html.push_str(&format!("<title>{} – {}</title>\n", escape(&report.title), escape(&range_text(report))));
+    html.push_str(&format!("<style>{}</style>\n</head>\n<body>\n", STYLE));
+
+    html.push_str(&format!("<h1>{}</h1>\n", escape(&report.title)));
+    html.push_str(&format!("<p class=\"summary\">{} · <strong>{}</strong> in {} sessions on {} days",
+        escape(&range_text(report)), report.total.time.text, report.total.sessions, report.total.days_worked));
+    if report.total.overtime.minutes > 0 {
+        html.push_str(&format!(" · work time <strong>{}</strong> · overtime <strong>{}</strong>",
+            report.total.work.text, report.total.overtime.text));
+    }
+    html.push_str("</p>\n");
+
+    if report.sessions.is_empty() {
+        html.push_str("<p>No sessions in this period.</p>\n");
+    } else {
+        html.push_str("<h2>Days</h2>\n");
+        html.push_str(&day_chart(report, &colors));
+        html.push_str("<p class=\"legend\">");
+        for (path, color) in report.columns.iter().zip(&colors) {
+            html.push_str(&format!("<span>{}{}</span>", swatch(color), escape(path)));
+        }
+        html.push_str("</p>\n");
+        html.push_str(&day_table(report));
+
+        html.push_str("<h2>Categories</h2>\n");
+        html.push_str(&category_table(report, &colors));
+
+        if !report.tags.is_empty() {
+            html.push_str("<h2>Tags</h2>\n");
+            html.push_str(&tag_table(report));
+        }
+
+        html.push_str("<h2>Sessions</h2>\n");
+        html.push_str(&session_table(report));
+    }
+
+    html.push_str(&format!("<footer>Generated by metron on {} ({})</footer>\n</body>\n</html>\n",
+        escape(&report.generated), escape(&report.timezone)));
+    html
+}
+
+/// One bar per day, stacked by top-level category
+fn day_chart(report: &Report, colors: &[String]) -> String {
+    const HEIGHT: f64 = 160.0;
+    const WIDTH: f64 = 900.0;
+    let max = report.days.iter().map(|d| d.time.minutes).max().unwrap_or(0).max(60) as f64;
+    let slot = WIDTH / report.days.len().max(1) as f64;
+    let bar = (slot * 0.7).max(1.0);
+    let labels = report.days.len() <= 31;
+
+    let mut svg = format!("<svg viewBox=\"0 0 {} {}\" width=\"100%\" role=\"img\" aria-label=\"Time per day\">\n",
+        WIDTH + 40.0, HEIGHT + 30.0);
+    svg.push_str(&format!("<line x1=\"40\" y1=\"{0}\" x2=\"{1}\" y2=\"{0}\" stroke=\"#ccc\"/>\n", HEIGHT, WIDTH + 40.0));
+    svg.push_str(&format!("<text x=\"0\" y=\"10\">{}</text>\n", Time::from(max as u32).text));
+
+    for (i, day) in report.days.iter().enumerate() {
+        let x = 40.0 + i as f64 * slot + (slot - bar) / 2.0;
+        let mut y = HEIGHT;
+        for (time, color) in day.columns.iter().zip(colors) {
+            if time.minutes == 0 {
+                continue;
+            }
+            let height = time.minutes as f64 / max * HEIGHT;
+            y -= height;
+            svg.push_str(&format!(
+                "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"><title>{} {}: {}</title></rect>\n",
+                x, y, bar, height, escape(color), day.date, escape(&day.weekday), time.text));
+        }
+        if labels {
+            svg.push_str(&format!("<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>\n",
+                x + bar / 2.0, HEIGHT + 14.0, day.date.format("%-d")));
+        }
+    }
+    svg.push_str("</svg>\n");
+    svg
+}
+
+/// A horizontal bar showing a share of the total
+fn share_bar(share: f64, color: &str) -> String {
+    format!("<svg width=\"120\" height=\"10\"><rect width=\"120\" height=\"10\" fill=\"#eee\"/>\
+        <rect width=\"{:.1}\" height=\"10\" fill=\"{}\"/></svg>", share.clamp(0.0, 100.0) * 1.2, escape(color))
+}
+
+fn day_table(report: &Report) -> String {
+    let mut html = String::from("<table>\n<tr><th>Date</th><th>Day</th>");
+    for column in &report.columns {
+        html.push_str(&format!("<th class=\"num\">{}</th>", escape(column)));
+    }
+    html.push_str("<th class=\"num\">Total</th></tr>\n");
+    for day in &report.days {
+        let class = if day.sessions == 0 { " class=\"empty\"" } else { "" };
+        html.push_str(&format!("<tr{}><td>{}</td><td>{}</td>", class, day.date, escape(&day.weekday)));
+        for time in &day.columns {
+            html.push_str(&format!("<td class=\"num\">{}</td>", amount(time)));
+        }
+        html.push_str(&format!("<td class=\"num\">{}</td></tr>\n", amount(&day.time)));
+    }
+    html.push_str("<tr class=\"total\"><td>Total</td><td></td>");
+    for i in 0..report.columns.len() {
+        let minutes: u32 = report.days.iter().map(|d| d.columns[i].minutes).sum();
+        html.push_str(&format!("<td class=\"num\">{}</td>", Time::from(minutes).text));
+    }
+    html.push_str(&format!("<td class=\"num\">{}</td></tr>\n</table>\n", report.total.time.text));
+    html
+}
+
+fn category_table(report: &Report, colors: &[String]) -> String {
+    let mut html = String::from("<table>\n<tr><th>Category</th><th class=\"num\">Sessions</th><th class=\"num\">Time</th>\
+        <th>Share</th><th class=\"num\">Weekly quota</th><th class=\"num\">Work time</th><th class=\"num\">Overtime</th></tr>\n");
+    for category in &report.categories {
+        let color = category.color.clone()
+            .or_else(|| {
+                report.columns.iter()
+                    .position(|p| category.path == *p || category.path.starts_with(&format!("{}/", p)))
+                    .map(|i| colors[i].clone())
+            })
+            .unwrap_or_else(|| PALETTE[0].to_string());
+        let name = if category.depth == 0 {
+            format!("<strong>{}</strong>", escape(&category.name))
+        } else {
+            escape(&category.name)
+        };
+        let title = category.description.as_ref().map(|d| format!(" title=\"{}\"", escape(d))).unwrap_or_default();
+        html.push_str(&format!(
+            "<tr><td style=\"padding-left: {:.1}em\"{}>{}{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td>\
+             <td>{} {:.1}%</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td></tr>\n",
+            0.6 + 1.5 * category.depth as f64, title, swatch(&color), name, category.sessions, category.time.text,
+            share_bar(category.share, &color), category.share,
+            category.weekly_quota.as_ref().map(|q| q.text.as_str()).unwrap_or("–"),
+            category.work.text, amount(&category.overtime)));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn tag_table(report: &Report) -> String {
+    let mut html = String::from("<table>\n<tr><th>Tag</th><th class=\"num\">Sessions</th><th class=\"num\">Time</th><th>Share</th></tr>\n");
+    for tag in &report.tags {
+        let color = tag.color.as_deref().unwrap_or(PALETTE[0]);
+        html.push_str(&format!("<tr><td>{}{}</td><td class=\"num\">{}</td><td class=\"num\">{}</td><td>{} {:.1}%</td></tr>\n",
+            swatch(color), escape(&tag.name), tag.sessions, tag.time.text, share_bar(tag.share, color), tag.share));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn session_table(report: &Report) -> String {
+    let mut html = String::from("<table>\n<tr><th>Date</th><th>Time</th><th>Title</th><th>Category</th><th>Tags</th>\
+        <th class=\"num\">Duration</th></tr>\n");
+    for session in &report.sessions {
+        html.push_str(&format!("<tr><td>{}</td><td>{}–{}</td><td>{}</td><td>{}</td><td>{}</td><td class=\"num\">{}</td></tr>\n",
+            session.date, session.start, session.end, escape(&session.title), escape(&session.category),
+            escape(&session.tags.join(", ")), session.time.text));
+    }
+    html.push_str("</table>\n");
+    html
+}
+
+fn swatch(color: &str) -> String {
+    format!("<span class=\"swatch\" style=\"background: {}\"></span>", escape(color))
+}
+
+fn amount(time: &Time) -> String {
+    if time.minutes == 0 { "–".to_string() } else { time.text.clone() }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
@@ -0,0 +1,123 @@
+//! Invoice summaries: billable time of one client as line items with rates.
+
+use super::Time;
+use crate::utils::format_money;
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use std::io::Write;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum InvoiceFormat {
+    Markdown,
+    Csv,
+}
+
+#[derive(Debug, Clone)]
+pub struct Invoice {
+    pub client: String, // category path
+    pub first_day: Option<NaiveDate>,
+    pub last_day: Option<NaiveDate>,
+    pub currency: Option<String>,
+    pub lines: Vec<LineItem>,
+    pub time: Time,
+    pub subtotal: u64, // in cents, like all amounts
+    pub tax_percent: Option<f64>,
+    pub tax: u64,
+    pub total: u64,
+}
+
+#[derive(Debug, Clone)]
+pub struct LineItem {
+    pub date: Option<NaiveDate>, // only for lines of single sessions
+    pub description: String,
+    pub time: Time,
+    pub rate: u64,
+    pub amount: u64,
+}
+
+impl Invoice {
+    fn money(&self, cents: u64) -> String {
+        format_money(cents, self.currency.as_deref())
+    }
+
+    fn period(&self) -> String {
+        match (self.first_day, self.last_day) {
+            (Some(first), Some(last)) => format!("{} to {}", first, last),
+            (Some(first), None) => format!("from {}", first),
+            (None, Some(last)) => format!("until {}", last),
+            (None, None) => "all time".to_string(),
+        }
+    }
+
+    fn tax_label(&self) -> Option<String> {
+        self.tax_percent.map(|percent| format!("Tax {}%", percent))
+    }
+
+    fn dated(&self) -> bool {
+        self.lines.iter().any(|l| l.date.is_some())
+    }
+}
+
+pub fn markdown(invoice: &Invoice) -> String {
+    let mut out = vec![
+        format!("# Invoice: {}", invoice.client.replace(['\n', '\r'], " ")),
+        String::new(),
+        format!("**Period:** {}  ", invoice.period()),
+        format!("**Billed time:** {}", invoice.time.text),
+        String::new(),
+    ];
+
+    // Summary rows leave the date column empty
+    let lead = if invoice.dated() { "| " } else { "" };
+    if invoice.dated() {
+        out.push("| Date | Description | Hours | Rate | Amount |".to_string());
+        out.push("| --- | --- | --: | --: | --: |".to_string());
+    } else {
+        out.push("| Description | Hours | Rate | Amount |".to_string());
+        out.push("| --- | --: | --: | --: |".to_string());
+    }
+    for line in &invoice.lines {
+        let date = line.date.map(|d| format!("| {} ", d)).unwrap_or_default();
+        out.push(format!("{}| {} | {:.2} | {} | {} |",
+            date, line.description.replace('|', "\\|").replace(['\n', '\r'], " "),
+            line.time.hours, invoice.money(line.rate), invoice.money(line.amount)));
+    }
+    out.push(format!("{}| **Subtotal** | **{:.2}** | | **{}** |", lead, invoice.time.hours, invoice.money(invoice.subtotal)));
+    if let Some(label) = invoice.tax_label() {
+        out.push(format!("{}| {} | | | {} |", lead, label, invoice.money(invoice.tax)));
+        out.push(format!("{}| **Total** | | | **{}** |", lead, invoice.money(invoice.total)));
+    }
+    out.push(String::new());
+    out.join("\n")
+}
+
+/// Line items followed by the subtotal, tax and total; amounts without currency
+pub fn csv(writer: impl Write, invoice: &Invoice) -> Result<(), String> {
+    let mut writer = ::csv::Writer::from_writer(writer);
+    let currency = invoice.currency.clone().unwrap_or_default();
+    let amount = |cents: u64| format_money(cents, None);
+
+    writer.write_record(["date", "description", "hours", "rate", "amount", "currency"]).map_err(|e| e.to_string())?;
+    for line in &invoice.lines {
+        writer.write_record([
+            line.date.map(|d| d.to_string()).unwrap_or_default(),
+            line.description.clone(),
+            format!("{:.2}", line.time.hours),
+            amount(line.rate),
+            amount(line.amount),
+            currency.clone(),
+        ]).map_err(|e| e.to_string())?;
+    }
+
+    let mut summary = vec![("Subtotal".to_string(), format!("{:.2}", invoice.time.hours), invoice.subtotal)];
+    if let Some(label) = invoice.tax_label() {
+        summary.push((label, String::new(), invoice.tax));
+        summary.push(("Total".to_string(), String::new(), invoice.total));
+    }
+    for (label, hours, cents) in summary {
+        writer.write_record([String::new(), label, hours, String::new(), amount(cents), currency.clone()])
+            .map_err(|e| e.to_string())?;
+    }
+
+    writer.flush().map_err(|e| e.to_string())
+}
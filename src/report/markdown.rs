@@ -0,0 +1,121 @@
+use super::{Report, Time};
+
+pub fn render(report: &Report) -> String {
+    let mut out = Vec::new();
+
+    out.push(format!("# {}", cell(&report.title)));
+    out.push(String::new());
+    out.push(format!("**Period:** {}  ", range_text(report)));
+    out.push(format!("**Total:** {} in {} sessions on {} days  ",
+        report.total.time.text, report.total.sessions, report.total.days_worked));
+    if report.total.overtime.minutes > 0 {
+        out.push(format!("**Work time:** {} · **Overtime:** {}  ", report.total.work.text, report.total.overtime.text));
+    }
+    out.push(format!("_Generated {} ({})_", report.generated, report.timezone));
+
+    if report.sessions.is_empty() {
+        out.push(String::new());
+        out.push("No sessions in this period.".to_string());
+        return finish(out);
+    }
+
+    out.push(String::new());
+    out.push("## Days".to_string());
+    out.push(String::new());
+    let mut header = vec!["Date".to_string(), "Day".to_string()];
+    header.extend(report.columns.iter().map(|c| cell(c)));
+    header.push("Total".to_string());
+    out.push(row(&header));
+    let mut align = vec!["---", "---"];
+    align.extend(report.columns.iter().map(|_| "--:"));
+    align.push("--:");
+    out.push(format!("| {} |", align.join(" | ")));
+    for day in &report.days {
+        let mut cells = vec![day.date.to_string(), day.weekday.clone()];
+        cells.extend(day.columns.iter().map(amount));
+        cells.push(amount(&day.time));
+        out.push(row(&cells));
+    }
+    let mut totals = vec!["**Total**".to_string(), String::new()];
+    totals.extend((0..report.columns.len()).map(|i| {
+        let minutes: u32 = report.days.iter().map(|d| d.columns[i].minutes).sum();
+        format!("**{}**", Time::from(minutes).text)
+    }));
+    totals.push(format!("**{}**", report.total.time.text));
+    out.push(row(&totals));
+
+    out.push(String::new());
+    out.push("## Categories".to_string());
+    out.push(String::new());
+    out.push("| Category | Sessions | Time | Share | Weekly quota | Work time | Overtime |".to_string());
+    out.push("| --- | --: | --: | --: | --: | --: | --: |".to_string());
+    for category in &report.categories {
+        // Non-breaking spaces keep the indentation of subcategories in rendered tables
+        let name = format!("{}{}", "\u{a0}\u{a0}\u{a0}\u{a0}".repeat(category.depth), cell(&category.name));
+        out.push(row(&[
+            if category.depth == 0 { format!("**{}**", name) } else { name },
+            category.sessions.to_string(),
+            category.time.text.clone(),
+            format!("{:.1}%", category.share),
+            category.weekly_quota.as_ref().map(|q| q.text.clone()).unwrap_or_else(|| "–".to_string()),
+            category.work.text.clone(),
+            amount(&category.overtime),
+        ]));
+    }
+
+    if !report.tags.is_empty() {
+        out.push(String::new());
+        out.push("## Tags".to_string());
+        out.push(String::new());
+        out.push("| Tag | Sessions | Time | Share |".to_string());
+        out.push("| --- | --: | --: | --: |".to_string());
+        for tag in &report.tags {
+            out.push(row(&[cell(&tag.name), tag.sessions.to_string(), tag.time.text.clone(), format!("{:.1}%", tag.share)]));
+        }
+    }
+
+    out.push(String::new());
+    out.push("## Sessions".to_string());
+    out.push(String::new());
+    out.push("| Date | Time | Title | Category | Tags | Duration |".to_string());
+    out.push("| --- | --- | --- | --- | --- | --: |".to_string());
+    for session in &report.sessions {
+        out.push(row(&[
+            session.date.to_string(),
+            format!("{}–{}", session.start, session.end),
+            cell(&session.title),
+            cell(&session.category),
+            cell(&session.tags.join(", ")),
+            session.time.text.clone(),
+        ]));
+    }
+
+    finish(out)
+}
+
+pub(super) fn range_text(report: &Report) -> String {
+    match (report.first_day, report.last_day) {
+        (Some(first), Some(last)) if first == last => first.to_string(),
+        (Some(first), Some(last)) => format!("{} to {}", first, last),
+        _ => report.period.clone(),
+    }
+}
+
+fn finish(mut lines: Vec<String>) -> String {
+    lines.push(String::new());
+    lines.join("\n")
+}
+
+fn row(cells: &[String]) -> String {
+    format!("| {} |", cells.join(" | "))
+}
+
+/// Empty days and categories without overtime show a dash instead of 0m
+fn amount(time: &Time) -> String {
+    if time.minutes == 0 { "–".to_string() } else { time.text.clone() }
+}
+
+/// Pipes and line breaks would end a table cell
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|").replace(['\n', '\r'], " ")
+}
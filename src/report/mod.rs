@@ -0,0 +1,112 @@
+//! Timesheet reports. `ReportManager` collects a `Report` for a range of days,
+//! which the renderers here turn into shareable documents. The `Report` is
+//! also the data model given to report templates, so every field is part of
+//! the documented template interface (see USAGE.md).
+
+pub mod html;
+pub mod invoice;
+pub mod markdown;
+pub mod template;
+
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Markdown,
+    Html,
+    /// The report data as templates see it
+    Json,
+}
+
+/// A duration in the forms templates need
+#[derive(Debug, Clone, Serialize)]
+pub struct Time {
+    pub minutes: u32,
+    pub hours: f64, // rounded to two decimals
+    pub text: String, // like 1h 30m
+}
+
+impl From<u32> for Time {
+    fn from(minutes: u32) -> Self {
+        Self {
+            minutes,
+            hours: (minutes as f64 / 60.0 * 100.0).round() / 100.0,
+            text: crate::utils::format_duration(minutes),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub title: String,
+    pub period: String, // the range as given, e.g. "month" or "2026-09-01 to 2026-09-30"
+    pub category: Option<String>, // category the report is limited to
+    pub first_day: Option<NaiveDate>,
+    pub last_day: Option<NaiveDate>,
+    pub generated: String, // time of generation, in the report's time zone
+    pub timezone: String,
+    /// Top-level categories, in the order of `Day::columns`
+    pub columns: Vec<String>,
+    pub days: Vec<Day>,
+    pub categories: Vec<CategoryTotal>,
+    pub tags: Vec<TagTotal>,
+    pub sessions: Vec<SessionRow>,
+    pub total: Totals,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Day {
+    pub date: NaiveDate,
+    pub weekday: String, // Mon, Tue, ...
+    pub sessions: usize,
+    pub time: Time,
+    /// Time per top-level category, in the order of `Report::columns`
+    pub columns: Vec<Time>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CategoryTotal {
+    pub name: String,
+    pub path: String,
+    pub depth: usize, // 0 for top-level categories (or the filtered category)
+    pub description: Option<String>,
+    pub color: Option<String>, // a CSS color name or #rrggbb
+    pub sessions: usize,
+    pub time: Time, // including subcategories
+    pub weekly_quota: Option<Time>,
+    pub work: Time, // time within the weekly quotas
+    pub overtime: Time, // time beyond them, counted week by week
+    pub share: f64, // percent of the report's total time
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TagTotal {
+    pub name: String,
+    pub color: Option<String>,
+    pub sessions: usize,
+    pub time: Time,
+    pub share: f64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SessionRow {
+    pub id: String,
+    pub date: NaiveDate,
+    pub start: String, // HH:MM
+    pub end: String,
+    pub title: String,
+    pub category: String, // path
+    pub tags: Vec<String>,
+    pub time: Time,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Totals {
+    pub sessions: usize,
+    pub days_worked: usize,
+    pub time: Time,
+    pub work: Time,
+    pub overtime: Time,
+}
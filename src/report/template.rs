@@ -0,0 +1,78 @@
+//! Reports rendered from user templates in `<config dir>/templates`. Templates
+//! use Jinja syntax (as in Tera) and get the `Report` as their context.
+
+use super::Report;
+use crate::utils::format_duration;
+use minijinja::{AutoEscape, Environment};
+use std::path::{Path, PathBuf};
+
+/// `$METRON_CONFIG_DIR`, or `metron` in the platform's config directory
+pub fn config_dir() -> Option<PathBuf> {
+    match std::env::var_os("METRON_CONFIG_DIR") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => dirs::config_dir().map(|dir| dir.join("metron")),
+    }
+}
+
+pub fn templates_dir() -> Option<PathBuf> {
+    config_dir().map(|dir| dir.join("templates"))
+}
+
+/// Names of the installed templates, as file names
+pub fn available() -> Vec<String> {
+    let Some(dir) = templates_dir() else { return Vec::new() };
+    let Ok(entries) = std::fs::read_dir(dir) else { return Vec::new() };
+    let mut names: Vec<String> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_file())
+        .filter_map(|e| e.file_name().into_string().ok())
+        .filter(|name| !name.starts_with('.'))
+        .collect();
+    names.sort();
+    names
+}
+
+/// Finds a template by file name, or by name without its extension
+/// (`client` finds `client.md`). Names can't leave the templates directory.
+pub fn find(name: &str) -> Result<PathBuf, String> {
+    if name.is_empty() || name.contains(['/', '\\']) || name.contains("..") || Path::new(name).is_absolute() {
+        return Err(format!("Invalid template name '{}', give a file name in the templates directory", name));
+    }
+    let dir = templates_dir().ok_or("No config directory found, set METRON_CONFIG_DIR")?;
+    let exact = dir.join(name);
+    if exact.is_file() {
+        return Ok(exact);
+    }
+
+    let installed = available();
+    let matches: Vec<&String> = installed.iter()
+        .filter(|file| Path::new(file).file_stem().is_some_and(|stem| stem == name))
+        .collect();
+    match matches.as_slice() {
+        [file] => Ok(dir.join(file)),
+        [] if installed.is_empty() => Err(format!("No template '{}', {} has no templates", name, dir.display())),
+        [] => Err(format!("No template '{}' in {} (available: {})", name, dir.display(), installed.join(", "))),
+        _ => Err(format!("'{}' matches {}, give the full file name",
+            name, matches.iter().map(|m| m.as_str()).collect::<Vec<_>>().join(" and "))),
+    }
+}
+
+/// Renders the report with a template file. Templates ending in .html or
+/// .htm escape everything they print unless it's marked `|safe`.
+pub fn render(report: &Report, path: &Path) -> Result<String, String> {
+    let source = std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+
+    let mut env = Environment::new();
+    env.set_auto_escape_callback(|name| match Path::new(name).extension().and_then(|e| e.to_str()) {
+        Some("html" | "htm") => AutoEscape::Html,
+        _ => AutoEscape::None,
+    });
+    env.set_keep_trailing_newline(true);
+    env.add_filter("duration", |minutes: u32| format_duration(minutes));
+    env.add_filter("hours", |minutes: u32| format!("{:.2}", minutes as f64 / 60.0));
+
+    env.add_template_owned(name.clone(), source).map_err(|e| e.to_string())?;
+    let template = env.get_template(&name).map_err(|e| e.to_string())?;
+    template.render(report).map_err(|e| e.to_string())
+}
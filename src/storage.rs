@@ -1,6 +1,9 @@
+use std::collections::HashMap;
 use std::fs;
 use std::path::Path;
-use crate::models::{MetronData, MetronError, Result};
+use serde_json::Value;
+use uuid::Uuid;
+use crate::models::{MetronData, MetronError, Result, DATA_VERSION};
 
 pub struct Storage {
     file_path: String,
@@ -12,8 +15,12 @@ impl Storage {
         let data = if Path::new(file_path).exists() {
             let contents = fs::read_to_string(file_path)
                 .map_err(|e| MetronError::StorageError(e.to_string()))?;
-            
-            serde_json::from_str(&contents)
+
+            let mut value: Value = serde_json::from_str(&contents)
+                .map_err(|e| MetronError::StorageError(e.to_string()))?;
+            migrate(&mut value)?;
+
+            serde_json::from_value(value)
                 .map_err(|e| MetronError::StorageError(e.to_string()))?
         } else {
             MetronData::new()
@@ -36,10 +43,175 @@ impl Storage {
     pub fn save(&self) -> Result<()> {
         let json = serde_json::to_string_pretty(&self.data)
             .map_err(|e| MetronError::StorageError(e.to_string()))?;
-        
+
         fs::write(&self.file_path, json)
             .map_err(|e| MetronError::StorageError(e.to_string()))?;
-        
+
         Ok(())
     }
 }
+
+/// Upgrades data written by older versions to the current layout, one version at a time
+fn migrate(value: &mut Value) -> Result<()> {
+    let version = value.get("version").and_then(Value::as_u64).unwrap_or(0) as u32;
+    if version > DATA_VERSION {
+        return Err(MetronError::StorageError(format!(
+            "data file version {} is newer than this metron supports ({})", version, DATA_VERSION
+        )));
+    }
+
+    if version < 1 {
+        // Quotas and targets were stored in hours
+        for category in array_mut(value, "categories") {
+            to_minutes(category, "category_weekly_quota");
+            to_minutes(category, "category_weekly_target");
+        }
+        to_minutes(value, "total_weekly_quota");
+    }
+
+    if version < 2 {
+        // Sessions referenced categories and tags by name; give every category
+        // and tag an ID and point sessions at it. Names that no longer exist
+        // are kept as they are so no history is lost.
+        let category_ids = assign_ids(value, "categories");
+        let tag_ids = assign_ids(value, "tags");
+
+        for session in array_mut(value, "sessions") {
+            let Some(session) = session.as_object_mut() else { continue };
+
+            if let Some(Value::String(name)) = session.remove("category") {
+                let id = category_ids.get(&name).cloned().unwrap_or(name);
+                session.insert("category_id".to_string(), Value::from(id));
+            }
+            if let Some(Value::Array(names)) = session.remove("tags") {
+                let ids: Vec<Value> = names.into_iter()
+                    .filter_map(|name| name.as_str().map(str::to_string))
+                    .map(|name| Value::from(tag_ids.get(&name).cloned().unwrap_or(name)))
+                    .collect();
+                session.insert("tag_ids".to_string(), Value::from(ids));
+            }
+        }
+    }
+
+    if let Some(object) = value.as_object_mut() {
+        object.insert("version".to_string(), Value::from(DATA_VERSION));
+    }
+    Ok(())
+}
+
+fn array_mut<'a>(value: &'a mut Value, field: &str) -> impl Iterator<Item = &'a mut Value> {
+    value.get_mut(field)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+}
+
+/// Gives every named entry in `field` a new ID, returning a name → ID map
+fn assign_ids(value: &mut Value, field: &str) -> HashMap<String, String> {
+    let mut ids = HashMap::new();
+    for entry in array_mut(value, field) {
+        let Some(entry) = entry.as_object_mut() else { continue };
+        let id = Uuid::new_v4().to_string();
+        if let Some(name) = entry.get("name").and_then(Value::as_str) {
+            ids.insert(name.to_string(), id.clone());
+        }
+        entry.insert("id".to_string(), Value::from(id));
+    }
+    ids
+}
+
+fn to_minutes(object: &mut Value, field: &str) {
+    if let Some(field) = object.get_mut(field) {
+        if let Some(hours) = field.as_u64() {
+            *field = Value::from(hours * 60);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Loads `contents` from a data file, returns the data and the file as saved again
+    fn load_and_save(contents: &str) -> Result<(MetronData, Value)> {
+        let path = std::env::temp_dir().join(format!("metron-test-{}.json", Uuid::new_v4()));
+        fs::write(&path, contents).unwrap();
+        let path_str = path.to_str().unwrap();
+        let loaded = Storage::new(path_str).and_then(|storage| {
+            storage.save()?;
+            Ok(storage.get_data().clone())
+        });
+        let saved = fs::read_to_string(&path).ok().and_then(|s| serde_json::from_str(&s).ok()).unwrap_or(Value::Null);
+        fs::remove_file(&path).ok();
+        loaded.map(|data| (data, saved))
+    }
+
+    const V0: &str = r#"{
+        "categories": [
+            {"name": "Acme", "category_weekly_quota": 10, "category_weekly_target": 4},
+            {"name": "Internal", "category_weekly_quota": 0}
+        ],
+        "tags": [{"name": "design"}, {"name": "meeting"}],
+        "sessions": [
+            {"id": "s1", "title": "Mockups", "category": "Acme", "tags": ["design", "meeting"],
+             "start": "2026-01-05T09:00:00Z", "end": "2026-01-05T10:30:00Z", "duration": 90},
+            {"id": "s2", "title": "Old", "category": "Gone", "tags": ["removed"],
+             "start": "2026-01-06T09:00:00Z", "end": null, "duration": 30}
+        ],
+        "total_weekly_quota": 40
+    }"#;
+
+    const V1: &str = r#"{
+        "version": 1,
+        "categories": [{"name": "Acme", "category_weekly_quota": 600}],
+        "tags": [{"name": "design"}],
+        "sessions": [
+            {"id": "s1", "title": "Mockups", "category": "Acme", "tags": ["design"],
+             "start": "2026-01-05T09:00:00Z", "end": "2026-01-05T10:30:00Z", "duration": 90}
+        ],
+        "total_weekly_quota": 2400
+    }"#;
+
+    #[test]
+    fn migrates_hours_and_names_of_version_0() {
+        let (data, saved) = load_and_save(V0).unwrap();
+
+        let acme = data.find_category("Acme").unwrap();
+        assert_eq!(acme.category_weekly_quota, 600);
+        assert_eq!(acme.category_weekly_target, Some(240));
+        assert_eq!(data.total_weekly_quota, Some(2400));
+
+        let session = &data.sessions[0];
+        assert_eq!(session.category_id, acme.id);
+        let tag_ids: Vec<&str> = ["design", "meeting"].iter().map(|n| data.find_tag(n).unwrap().id.as_str()).collect();
+        assert_eq!(session.tag_ids, tag_ids);
+        assert_eq!(session.duration, 90);
+
+        // Names that no longer exist are kept
+        assert_eq!(data.sessions[1].category_id, "Gone");
+        assert_eq!(data.sessions[1].tag_ids, vec!["removed"]);
+
+        assert_eq!(saved["version"], Value::from(DATA_VERSION));
+    }
+
+    #[test]
+    fn migrates_names_of_version_1() {
+        let (data, saved) = load_and_save(V1).unwrap();
+
+        let acme = data.find_category("Acme").unwrap();
+        assert_eq!(acme.category_weekly_quota, 600);
+        assert_eq!(data.total_weekly_quota, Some(2400));
+        assert!(Uuid::parse_str(&acme.id).is_ok());
+        assert_eq!(data.sessions[0].category_id, acme.id);
+        assert_eq!(data.sessions[0].tag_ids, vec![data.find_tag("design").unwrap().id.clone()]);
+
+        assert_eq!(saved["version"], Value::from(DATA_VERSION));
+        assert!(saved["sessions"][0].get("category").is_none());
+    }
+
+    #[test]
+    fn refuses_newer_versions() {
+        let newer = format!(r#"{{"version": {}, "categories": [], "tags": [], "sessions": [], "total_weekly_quota": null}}"#, DATA_VERSION + 1);
+        assert!(matches!(load_and_save(&newer), Err(MetronError::StorageError(_))));
+    }
+}
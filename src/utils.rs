@@ -0,0 +1,330 @@
+use crate::models::{BreakRule, BLOCK_SIZES};
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// Start (Monday 00:00) and end (next Monday 00:00) of the week containing `at`
+pub fn week_bounds(at: DateTime<Utc>) -> (DateTime<Utc>, DateTime<Utc>) {
+    let monday = at.date_naive() - Duration::days(at.weekday().num_days_from_monday() as i64);
+    let start = Utc.from_utc_datetime(&monday.and_hms_opt(0, 0, 0).unwrap());
+    (start, start + Duration::days(7))
+}
+
+/// Whether a session started within the named period (day, week, month, year) around `now`
+pub fn is_in_period(session_start: DateTime<Utc>, period: &str, now: DateTime<Utc>) -> bool {
+    match period.to_lowercase().as_str() {
+        "day" | "daily" => {
+            session_start.date_naive() == now.date_naive()
+        },
+        "week" | "weekly" => {
+            let (week_start, _) = week_bounds(now);
+            session_start >= week_start && session_start <= now
+        },
+        "month" | "monthly" => {
+            session_start.year() == now.year() && session_start.month() == now.month()
+        },
+        "year" | "yearly" => {
+            session_start.year() == now.year()
+        },
+        _ => {
+            eprintln!("Unknown period '{}', using weekly", period);
+            let (week_start, _) = week_bounds(now);
+            session_start >= week_start && session_start <= now
+        }
+    }
+}
+
+/// Sessions to include, by period name (day, week, month, year) around now
+/// and/or by first and last day (inclusive, UTC)
+#[derive(Debug, Clone, Default)]
+pub struct DateRange {
+    pub period: Option<String>,
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+}
+
+impl DateRange {
+    /// Uses `period` if neither a period nor days were given
+    pub fn or_period(self, period: &str) -> Self {
+        if self.period.is_none() && self.from.is_none() && self.to.is_none() {
+            Self { period: Some(period.to_string()), ..self }
+        } else {
+            self
+        }
+    }
+
+    /// The calendar month starting on `first_day`
+    pub fn month(first_day: NaiveDate) -> Self {
+        let last_day = first_day.checked_add_months(chrono::Months::new(1)).and_then(|d| d.pred_opt());
+        Self { period: None, from: Some(first_day), to: last_day }
+    }
+
+    /// First and last day the range can include, where it is bounded
+    pub fn bounds(&self, now: DateTime<Utc>) -> (Option<NaiveDate>, Option<NaiveDate>) {
+        let today = now.date_naive();
+        let period_start = self.period.as_deref().map(|p| match p.to_lowercase().as_str() {
+            "day" | "daily" => today,
+            "month" | "monthly" => today.with_day(1).unwrap_or(today),
+            "year" | "yearly" => today.with_ordinal(1).unwrap_or(today),
+            _ => week_bounds(now).0.date_naive(),
+        });
+        let first = match (period_start, self.from) {
+            (Some(a), Some(b)) => Some(a.max(b)),
+            (a, b) => a.or(b),
+        };
+        let last = match (self.period.as_ref().map(|_| today), self.to) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+        (first, last)
+    }
+
+    pub fn is_week(&self) -> bool {
+        self.period.as_deref().is_some_and(|p| matches!(p.to_lowercase().as_str(), "week" | "weekly"))
+    }
+
+    pub fn contains(&self, session_start: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+        let date = session_start.date_naive();
+        self.period.as_ref().is_none_or(|p| is_in_period(session_start, p, now))
+            && self.from.is_none_or(|from| date >= from)
+            && self.to.is_none_or(|to| date <= to)
+    }
+
+    /// Like `contains`, but going by the session's date in `timezone`, with
+    /// periods counted from today in that time zone
+    pub fn contains_local(&self, session_start: DateTime<Utc>, now: DateTime<Utc>, timezone: Tz) -> bool {
+        let date = session_start.with_timezone(&timezone).date_naive();
+        let (first, last) = self.bounds(local_now(now, timezone));
+        first.is_none_or(|first| date >= first) && last.is_none_or(|last| date <= last)
+    }
+}
+
+/// `now` with the date and time it has in `timezone`, for date arithmetic on local days
+pub fn local_now(now: DateTime<Utc>, timezone: Tz) -> DateTime<Utc> {
+    now.with_timezone(&timezone).naive_local().and_utc()
+}
+
+impl std::fmt::Display for DateRange {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut parts: Vec<String> = self.period.iter().cloned().collect();
+        match (self.from, self.to) {
+            (Some(from), Some(to)) => parts.push(format!("{} to {}", from, to)),
+            (Some(from), None) => parts.push(format!("from {}", from)),
+            (None, Some(to)) => parts.push(format!("until {}", to)),
+            (None, None) => {}
+        }
+        if parts.is_empty() {
+            parts.push("all time".to_string());
+        }
+        write!(f, "{}", parts.join(", "))
+    }
+}
+
+/// Parses a day given as `YYYY-MM-DD`
+pub fn parse_date(s: &str) -> std::result::Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(s.trim(), "%Y-%m-%d")
+        .map_err(|_| format!("invalid date '{}', expected YYYY-MM-DD", s))
+}
+
+/// Parses a month given as `YYYY-MM`, returning its first day
+pub fn parse_month(s: &str) -> std::result::Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(&format!("{}-01", s.trim()), "%Y-%m-%d")
+        .map_err(|_| format!("invalid month '{}', expected YYYY-MM", s))
+}
+
+/// Parses an hourly rate like `85`, `85.50`, `85 EUR` or `EUR 85.50`
+pub fn parse_rate(s: &str) -> std::result::Result<crate::models::Rate, String> {
+    let error = || format!("invalid rate '{}', expected an amount like 85 or 85.50 EUR", s);
+    let text = s.trim();
+    let amount_start = text.find(|c: char| c.is_ascii_digit()).ok_or_else(error)?;
+    let amount_end = text[amount_start..].find(|c: char| !c.is_ascii_digit() && c != '.')
+        .map_or(text.len(), |i| amount_start + i);
+    let currency = format!("{}{}", &text[..amount_start], &text[amount_end..]).trim().to_uppercase();
+    if !currency.is_empty() && (currency.len() != 3 || !currency.chars().all(|c| c.is_ascii_alphabetic())) {
+        return Err(error());
+    }
+
+    let (whole, fraction) = text[amount_start..amount_end].split_once('.').unwrap_or((&text[amount_start..amount_end], ""));
+    if fraction.len() > 2 || fraction.contains('.') {
+        return Err(error());
+    }
+    let whole: u64 = whole.parse().map_err(|_| error())?;
+    let fraction: u64 = if fraction.is_empty() { 0 } else { format!("{:0<2}", fraction).parse().map_err(|_| error())? };
+    let cents = whole.checked_mul(100).and_then(|c| c.checked_add(fraction)).ok_or_else(error)?;
+    Ok(crate::models::Rate {
+        cents,
+        currency: (!currency.is_empty()).then_some(currency),
+    })
+}
+
+/// Parses a tax rate in percent, from 0 to 100
+pub fn parse_tax(s: &str) -> std::result::Result<f64, String> {
+    s.trim().parse::<f64>().ok()
+        .filter(|percent| percent.is_finite() && (0.0..=100.0).contains(percent))
+        .ok_or_else(|| format!("invalid tax '{}', expected a percentage from 0 to 100, e.g. 19", s))
+}
+
+/// Parses a time of day given as `HH:MM`
+pub fn parse_time(s: &str) -> std::result::Result<NaiveTime, String> {
+    NaiveTime::parse_from_str(s.trim(), "%H:%M")
+        .map_err(|_| format!("invalid time '{}', expected HH:MM", s))
+}
+
+/// Parses an IANA time zone name such as `Europe/Berlin` or `UTC`
+pub fn parse_timezone(s: &str) -> std::result::Result<Tz, String> {
+    s.trim().parse::<Tz>().map_err(|_| format!("unknown time zone '{}', expected e.g. Europe/Berlin", s))
+}
+
+/// Checks a chrono/strftime format such as `%d.%m.%Y %H:%M`
+pub fn parse_time_format(s: &str) -> std::result::Result<String, String> {
+    use chrono::format::{Item, StrftimeItems};
+
+    if StrftimeItems::new(s).any(|item| matches!(item, Item::Error)) {
+        return Err(format!("invalid date format '{}', expected e.g. %Y-%m-%d %H:%M", s));
+    }
+    Ok(s.to_string())
+}
+
+/// Parses a point in time given on the command line, either RFC 3339 or
+/// `YYYY-MM-DD HH:MM` (interpreted as UTC, like all stored timestamps)
+pub fn parse_datetime(s: &str) -> std::result::Result<DateTime<Utc>, String> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
+        return Ok(dt.with_timezone(&Utc));
+    }
+    for format in ["%Y-%m-%d %H:%M", "%Y-%m-%dT%H:%M", "%Y-%m-%d %H:%M:%S"] {
+        if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(s, format) {
+            return Ok(Utc.from_utc_datetime(&naive));
+        }
+    }
+    Err(format!("invalid date/time '{}', expected YYYY-MM-DD HH:MM", s))
+}
+
+/// Parses a break rule given as `AFTER:BREAK`, e.g. `6h:30m` or `360:30`
+pub fn parse_break_rule(s: &str) -> std::result::Result<BreakRule, String> {
+    let (after, length) = s.split_once(':')
+        .ok_or_else(|| format!("invalid break rule '{}', expected AFTER:BREAK, e.g. 6h:30m", s))?;
+    let after_minutes = parse_minutes(after)?;
+    let break_minutes = parse_minutes(length)?;
+    Ok(BreakRule { after_minutes, break_minutes })
+}
+
+/// Parses a timeblock size, which must be one of `BLOCK_SIZES`
+pub fn parse_block_size(s: &str) -> std::result::Result<u32, String> {
+    let minutes: u32 = s.trim().parse().map_err(|_| format!("invalid block size '{}'", s))?;
+    if !BLOCK_SIZES.contains(&minutes) {
+        let allowed: Vec<String> = BLOCK_SIZES.iter().map(|b| b.to_string()).collect();
+        return Err(format!("block size must be one of {} minutes", allowed.join(", ")));
+    }
+    Ok(minutes)
+}
+
+/// Parses a human duration such as `1h30m`, `1h 30m`, `90m`, `1.5h` or `45min` into minutes.
+/// A bare number is taken in `bare_unit_minutes` (1 for minutes, 60 for hours).
+fn parse_duration(s: &str, bare_unit_minutes: f64) -> std::result::Result<u32, String> {
+    let invalid = || format!("invalid duration '{}', expected e.g. 1h30m, 90m or 1.5h", s);
+    let input = s.trim().to_lowercase().replace(' ', "");
+    if input.is_empty() {
+        return Err(invalid());
+    }
+
+    if let Ok(value) = input.parse::<f64>() {
+        return to_minutes(value * bare_unit_minutes).ok_or_else(invalid);
+    }
+
+    let mut total = 0.0;
+    let mut rest = input.as_str();
+    while !rest.is_empty() {
+        let number_len = rest.find(|c: char| !c.is_ascii_digit() && c != '.').ok_or_else(invalid)?;
+        let value: f64 = rest[..number_len].parse().map_err(|_| invalid())?;
+        rest = &rest[number_len..];
+
+        let unit_len = rest.find(|c: char| c.is_ascii_digit() || c == '.').unwrap_or(rest.len());
+        let factor = match &rest[..unit_len] {
+            "h" | "hr" | "hrs" | "hour" | "hours" => 60.0,
+            "m" | "min" | "mins" | "minute" | "minutes" => 1.0,
+            _ => return Err(invalid()),
+        };
+        rest = &rest[unit_len..];
+        total += value * factor;
+    }
+
+    to_minutes(total).ok_or_else(invalid)
+}
+
+/// Rejects negative, non-finite and fractional-minute results
+fn to_minutes(value: f64) -> Option<u32> {
+    let rounded = value.round();
+    if !value.is_finite() || value < 0.0 || (value - rounded).abs() > 1e-6 || rounded > u32::MAX as f64 {
+        return None;
+    }
+    Some(rounded as u32)
+}
+
+/// Parses a session duration; bare numbers are minutes
+pub fn parse_minutes(s: &str) -> std::result::Result<u32, String> {
+    parse_duration(s, 1.0)
+}
+
+/// Parses a quota in minutes; bare numbers are hours
+pub fn parse_quota(s: &str) -> std::result::Result<u32, String> {
+    parse_duration(s, 60.0)
+}
+
+/// Colors that can be given by name; anything else must be a `#rrggbb` hex code
+pub const COLOR_NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+/// Parses a color name or `#rrggbb` hex code; `none` gives an empty string, which removes a color
+pub fn parse_color(s: &str) -> std::result::Result<String, String> {
+    let color = s.trim().to_lowercase();
+    if color == "none" {
+        return Ok(String::new());
+    }
+    if COLOR_NAMES.contains(&color.as_str()) || hex_color(&color).is_some() {
+        return Ok(color);
+    }
+    Err(format!("invalid color '{}', expected one of {} or #rrggbb", s, COLOR_NAMES.join(", ")))
+}
+
+fn hex_color(color: &str) -> Option<(u8, u8, u8)> {
+    let hex = color.strip_prefix('#').filter(|h| h.len() == 6 && h.chars().all(|c| c.is_ascii_hexdigit()))?;
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+    Some((channel(0)?, channel(2)?, channel(4)?))
+}
+
+/// Wraps text in ANSI color codes when printing to a terminal, unless `NO_COLOR` is set
+pub fn paint(text: &str, color: Option<&str>) -> String {
+    use std::io::IsTerminal;
+
+    let Some(color) = color else { return text.to_string() };
+    if std::env::var_os("NO_COLOR").is_some() || !std::io::stdout().is_terminal() {
+        return text.to_string();
+    }
+    let code = match (COLOR_NAMES.iter().position(|c| *c == color), hex_color(color)) {
+        (Some(index), _) => format!("{}", 30 + index),
+        (None, Some((r, g, b))) => format!("38;2;{};{};{}", r, g, b),
+        (None, None) => return text.to_string(),
+    };
+    format!("\x1b[{}m{}\x1b[0m", code, text)
+}
+
+/// Treats an empty command-line value as "remove"
+pub fn non_empty(value: String) -> Option<String> {
+    Some(value).filter(|v| !v.trim().is_empty())
+}
+
+/// Formats cents as an amount with two decimals, followed by the currency if known
+pub fn format_money(cents: u64, currency: Option<&str>) -> String {
+    let amount = format!("{}.{:02}", cents / 100, cents % 100);
+    match currency {
+        Some(currency) => format!("{} {}", amount, currency),
+        None => amount,
+    }
+}
+
+/// Formats minutes as e.g. `1h 30m`, `2h` or `45m`
+pub fn format_duration(minutes: u32) -> String {
+    match (minutes / 60, minutes % 60) {
+        (0, m) => format!("{}m", m),
+        (h, 0) => format!("{}h", h),
+        (h, m) => format!("{}h {}m", h, m),
+    }
+}